@@ -0,0 +1,189 @@
+//! Benchmarks of the formatting, to run with `cargo bench --features bench`
+#[macro_use]
+extern crate maplit;
+
+use address_formatter::bench::Stages;
+use address_formatter::{BorrowedPlace, Component::*, Configuration, Formatter, Place};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+/// representative places, with the template they use
+fn places() -> Vec<(&'static str, Place)> {
+    vec![
+        (
+            "FR",
+            hashmap!(
+                City => "Toulouse",
+                Country => "France",
+                CountryCode => "FR",
+                County => "Toulouse",
+                HouseNumber => "17",
+                Postcode => "31000",
+                Road => "Rue du Médecin-Colonel Calbairac",
+                State => "Occitanie",
+                Suburb => "Toulouse Ouest",
+            )
+            .into(),
+        ),
+        (
+            "DE",
+            hashmap!(
+                City => "Berlin",
+                Country => "Deutschland",
+                CountryCode => "DE",
+                HouseNumber => "77",
+                Postcode => "10117",
+                Road => "Unter den Linden",
+                State => "Berlin",
+            )
+            .into(),
+        ),
+        (
+            "US",
+            hashmap!(
+                Attention => "Brooklyn Heights Library",
+                City => "Brooklyn",
+                Country => "United States of America",
+                CountryCode => "US",
+                HouseNumber => "301",
+                Postcode => "11201",
+                Road => "Hicks Street",
+                State => "New York",
+            )
+            .into(),
+        ),
+        (
+            "GB",
+            hashmap!(
+                City => "London",
+                Country => "United Kingdom",
+                CountryCode => "GB",
+                HouseNumber => "10",
+                Postcode => "SW1A 2AA",
+                Road => "Downing Street",
+                State => "England",
+                StateDistrict => "Greater London",
+            )
+            .into(),
+        ),
+        (
+            "JP",
+            hashmap!(
+                City => "千代田区",
+                Country => "日本",
+                CountryCode => "JP",
+                HouseNumber => "1-1",
+                Postcode => "100-8111",
+                Road => "千代田",
+                State => "東京都",
+            )
+            .into(),
+        ),
+        (
+            // neither a road nor a postcode, the fallback template is used
+            "fallback",
+            hashmap!(
+                City => "Papeete",
+                Country => "Polynésie française",
+                CountryCode => "PF",
+                Suburb => "Mamao",
+            )
+            .into(),
+        ),
+        (
+            // Saint Barthélemy uses the French template and rules
+            "use_country",
+            hashmap!(
+                City => "Gustavia",
+                CountryCode => "BL",
+                HouseNumber => "5",
+                Postcode => "97133",
+                Road => "Rue Jeanne d'Arc",
+            )
+            .into(),
+        ),
+    ]
+}
+
+fn load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("load");
+    group.sample_size(10);
+    group.bench_function("Formatter::default", |b| b.iter(Formatter::default));
+    group.finish();
+}
+
+fn format(c: &mut Criterion) {
+    let formatter = Formatter::default();
+    let mut group = c.benchmark_group("format");
+    for (name, place) in places() {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || place.clone(),
+                |place| formatter.format(place),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.bench_function("FR abbreviated", |b| {
+        let place = places()[0].1.clone();
+        let conf = Configuration {
+            abbreviate: Some(true),
+            ..Default::default()
+        };
+        let mut text = String::new();
+        b.iter(|| {
+            text.clear();
+            formatter.format_into(&place, &conf, &mut text)
+        })
+    });
+    group.finish();
+}
+
+/// each stage of the pipeline, on the output of the previous one
+fn stages(c: &mut Criterion) {
+    let formatter = Formatter::default();
+    for (name, place) in places() {
+        let stages = Stages::new(&formatter, &place, Configuration::default());
+        let original = BorrowedPlace::from(&place);
+        let mut cleaned = original.clone();
+        stages.sanity_clean(&mut cleaned);
+        let mut preformatted = cleaned.clone();
+        stages.preformat(&mut preformatted).unwrap();
+        let mut rendered = String::new();
+        stages.render(&preformatted, &mut rendered);
+
+        let mut group = c.benchmark_group(format!("stages/{}", name));
+        group.bench_function("sanity_clean_place", |b| {
+            b.iter_batched_ref(
+                || original.clone(),
+                |place| stages.sanity_clean(place),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("preformat", |b| {
+            b.iter_batched_ref(
+                || cleaned.clone(),
+                |place| stages.preformat(place),
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("render", |b| {
+            let mut text = String::new();
+            b.iter(|| {
+                text.clear();
+                stages.render(&preformatted, &mut text)
+            })
+        });
+        group.bench_function("cleanup_rendered", |b| {
+            let mut scratch = String::new();
+            b.iter_batched_ref(
+                || rendered.clone(),
+                |text| stages.cleanup(text, &mut scratch),
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, load, format, stages);
+criterion_main!(benches);
@@ -0,0 +1,144 @@
+/*
+ * C API of the address-formatter crate (built with the `ffi` feature).
+ *
+ * All the strings are nul terminated UTF-8 strings.
+ * The strings returned by the library must be freed with address_formatter_string_free.
+ * When a function fails, it returns NULL and the reason can be read with address_formatter_last_error.
+ */
+
+#ifndef ADDRESS_FORMATTER_H
+#define ADDRESS_FORMATTER_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit (see cbindgen.toml) */
+
+#include <stddef.h>
+
+/**
+ * A formatter and the place builder using the same configuration
+ */
+typedef struct AddressFormatter AddressFormatter;
+
+/**
+ * A place to format
+ */
+typedef struct AddressFormatterPlace AddressFormatterPlace;
+
+/**
+ * The formatting options, the C equivalent of the [`Configuration`](../struct.Configuration.html).
+ *
+ * The null strings are considered as not set.
+ */
+typedef struct {
+  /**
+   * force the use of a given country
+   */
+  const char *country_code;
+  /**
+   * use the abbreviations if not 0
+   */
+  int abbreviate;
+  /**
+   * force the use of a given language for the abbreviations
+   */
+  const char *language;
+  /**
+   * country code of the sender, the country is not written for domestic mail
+   */
+  const char *origin_country;
+} AddressFormatterOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Get the message of the last error that occurred in the current thread,
+ * or a null pointer if there was none.
+ *
+ * The message is owned by the library and is valid until the next failing call in the same thread.
+ */
+const char *address_formatter_last_error(void);
+
+/**
+ * Create a formatter with the opencage configuration embedded in the library.
+ *
+ * Returns null if the configuration is invalid. The formatter must be freed with
+ * [`address_formatter_free`](fn.address_formatter_free.html).
+ */
+AddressFormatter *address_formatter_new(void);
+
+/**
+ * Create a formatter with the opencage configuration read from a directory.
+ *
+ * Returns null if the configuration cannot be read. The formatter must be freed with
+ * [`address_formatter_free`](fn.address_formatter_free.html).
+ *
+ * # Safety
+ *
+ * `config_dir` must be a valid nul terminated string
+ */
+AddressFormatter *address_formatter_from_config_dir(const char *config_dir);
+
+/**
+ * Free a formatter.
+ *
+ * # Safety
+ *
+ * `formatter` must have been created by this library (or be null), and must not be used afterward
+ */
+void address_formatter_free(AddressFormatter *formatter);
+
+/**
+ * Build a place from `len` keys and values, the keys being the component names
+ * (like `road` or `country_code`) or one of their aliases (like `street`).
+ *
+ * Returns null if a key or a value is not a valid string. The place must be freed with
+ * [`address_formatter_place_free`](fn.address_formatter_place_free.html).
+ *
+ * # Safety
+ *
+ * `formatter` must be a valid formatter, `keys` and `values` must be arrays of `len`
+ * valid nul terminated strings (the null values are ignored)
+ */
+AddressFormatterPlace *address_formatter_place_new(const AddressFormatter *formatter,
+                                                   const char *const *keys,
+                                                   const char *const *values,
+                                                   size_t len);
+
+/**
+ * Free a place.
+ *
+ * # Safety
+ *
+ * `place` must have been created by this library (or be null), and must not be used afterward
+ */
+void address_formatter_place_free(AddressFormatterPlace *place);
+
+/**
+ * Format a place, the options can be null to use the default ones.
+ *
+ * Returns null if the place cannot be formatted. The returned string must be freed with
+ * [`address_formatter_string_free`](fn.address_formatter_string_free.html).
+ *
+ * # Safety
+ *
+ * `formatter` and `place` must be valid, and `options` must be null or valid
+ */
+char *address_formatter_format(const AddressFormatter *formatter,
+                               const AddressFormatterPlace *place,
+                               const AddressFormatterOptions *options);
+
+/**
+ * Free a string returned by the library.
+ *
+ * # Safety
+ *
+ * `s` must have been returned by this library (or be null), and must not be used afterward
+ */
+void address_formatter_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ADDRESS_FORMATTER_H */
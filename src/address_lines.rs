@@ -0,0 +1,192 @@
+use crate::{
+    AnnotatedAddress, BorrowedPlace, Component, Configuration, CountryCode, Formatter, Place,
+};
+use failure::Error;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// the components placing a line after the street lines
+const LOCALITY_COMPONENTS: [Component; 13] = [
+    Component::City,
+    Component::Town,
+    Component::Village,
+    Component::Postcode,
+    Component::StateDistrict,
+    Component::State,
+    Component::StateCode,
+    Component::County,
+    Component::CountyCode,
+    Component::Island,
+    Component::Region,
+    Component::Country,
+    Component::Continent,
+];
+
+/// A [`Place`](struct.Place.html) split in the fields of the payment and shipping APIs
+/// (like `line1`, `line2`, `city`, `state`, `postal_code` and `country`),
+/// made by [`Formatter::address_lines`](struct.Formatter.html#method.address_lines).
+///
+/// ```
+/// # #[macro_use] extern crate maplit;
+/// # fn main() {
+///    use address_formatter::Component::*;
+///
+///    let lines = address_formatter::FORMATTER
+///        .address_lines(
+///            hashmap!(
+///                Attention => "Brooklyn Heights Library",
+///                City => "Brooklyn",
+///                CountryCode => "US",
+///                HouseNumber => "301",
+///                Postcode => "11201",
+///                Road => "Hicks Street",
+///                State => "New York",
+///                StateCode => "NY",
+///            ),
+///            Default::default(),
+///        )
+///        .unwrap();
+///
+///    assert_eq!(lines.line1, "301 Hicks Street");
+///    assert_eq!(lines.line2.as_deref(), Some("Brooklyn Heights Library"));
+///    assert_eq!(lines.city.as_deref(), Some("Brooklyn"));
+///    // the state code is used in the US
+///    assert_eq!(lines.state.as_deref(), Some("NY"));
+///    assert_eq!(lines.postal_code.as_deref(), Some("11201"));
+///    assert_eq!(lines.country.as_deref(), Some("US"));
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressLines {
+    /// the street line (with the house number)
+    pub line1: String,
+    /// the other lines before the locality (like a building or a recipient), separated by ", "
+    pub line2: Option<String>,
+    /// the city (or the town, or the village)
+    pub city: Option<String>,
+    /// the state, as written in the country (either its name or its code)
+    pub state: Option<String>,
+    /// the postal code
+    pub postal_code: Option<String>,
+    /// the ISO 3166-1 code of the country, as found by the formatter
+    /// (or the displayed country if the code is unknown or invalid)
+    pub country: Option<String>,
+}
+
+impl Formatter {
+    /// split a [`Place`](struct.Place.html) in [`AddressLines`](struct.AddressLines.html).
+    ///
+    /// The country's template decides what goes where: the lines before the locality line are the street lines,
+    /// the one with the road being `line1`, and the others (like the `House` or the `Attention`) being `line2`.
+    /// The locality fields are the values displayed by the template (like the `StateCode` instead of the `State`).
+    pub fn address_lines(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<AddressLines, Error> {
+        let addr = into_addr.into();
+        let annotated = self.format_annotated_with_config(addr.clone(), conf.clone())?;
+        let text = &annotated.text;
+
+        let lines = line_ranges(text);
+        let street_lines = lines
+            .iter()
+            .take_while(|line| {
+                !annotated
+                    .spans
+                    .iter()
+                    .any(|(c, r)| LOCALITY_COMPONENTS.contains(c) && contains(line, r))
+            })
+            .collect::<Vec<_>>();
+        let is_road_line = |line: &Range<usize>| {
+            annotated.spans.iter().any(|(c, r)| {
+                (*c == Component::Road || *c == Component::HouseNumber) && contains(line, r)
+            })
+        };
+
+        let (line1, others): (String, Vec<&Range<usize>>) =
+            match street_lines.iter().position(|l| is_road_line(l)) {
+                Some(i) => (
+                    text[street_lines[i].clone()].to_owned(),
+                    street_lines
+                        .iter()
+                        .enumerate()
+                        .filter(|(j, _)| *j != i)
+                        .map(|(_, l)| *l)
+                        .collect(),
+                ),
+                None => {
+                    // the template can put the road on the locality line
+                    let street = annotated.street();
+                    if street.is_empty() && !street_lines.is_empty() {
+                        (
+                            text[street_lines[0].clone()].to_owned(),
+                            street_lines[1..].to_vec(),
+                        )
+                    } else {
+                        (street, street_lines)
+                    }
+                }
+            };
+        let line2 = others
+            .iter()
+            .map(|l| &text[(*l).clone()])
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        // the country code found by the formatter, the territories using the rules of another country
+        // being attached to this country (like Guadeloupe to France)
+        let mut borrowed = BorrowedPlace::from(&addr);
+        let country_code = self.find_country_code(&mut borrowed, &conf);
+        let country = self
+            .find_rules(&country_code)
+            .change_country_code
+            .as_deref()
+            .and_then(|c| CountryCode::from_str(c).ok())
+            .or(country_code)
+            .map(|c| c.as_str().to_owned())
+            .or_else(|| displayed(&annotated, &[Component::Country]));
+
+        Ok(AddressLines {
+            line1,
+            line2: Some(line2).filter(|l| !l.is_empty()),
+            city: displayed(
+                &annotated,
+                &[Component::City, Component::Town, Component::Village],
+            ),
+            state: displayed(&annotated, &[Component::State, Component::StateCode]),
+            postal_code: displayed(&annotated, &[Component::Postcode]),
+            country,
+        })
+    }
+}
+
+/// the byte ranges of the (non empty) lines of a text
+fn line_ranges(text: &str) -> Vec<Range<usize>> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for (i, _) in text.match_indices('\n') {
+        if i > start {
+            lines.push(start..i);
+        }
+        start = i + 1;
+    }
+    if start < text.len() {
+        lines.push(start..text.len());
+    }
+    lines
+}
+
+fn contains(line: &Range<usize>, span: &Range<usize>) -> bool {
+    line.start <= span.start && span.end <= line.end
+}
+
+/// the text of the first of the components displayed in the formatted address
+fn displayed(annotated: &AnnotatedAddress, components: &[Component]) -> Option<String> {
+    annotated
+        .spans
+        .iter()
+        .find(|(c, _)| components.contains(c))
+        .map(|(_, r)| annotated.text[r.clone()].to_owned())
+}
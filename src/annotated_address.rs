@@ -0,0 +1,333 @@
+use crate::formatter::RenderedText;
+use crate::{BorrowedPlace, Component};
+use itertools::Itertools;
+use regex::Regex;
+use std::borrow::Cow;
+use std::ops::Range;
+use strum::IntoEnumIterator;
+
+/// first marker used to tag the beginning of a component's value in the rendered text.
+/// The marker of a component is `COMPONENT_START + <index of the component>`
+/// (those are unicode private use characters, so they should not be in the data)
+const COMPONENT_START: u32 = 0xE000;
+/// marker used to tag the end of a component's value in the rendered text
+const COMPONENT_END: char = '\u{E0FF}';
+
+/// A [`Place`](struct.Place.html) formatted by the [`Formatter`](struct.Formatter.html),
+/// with the part of the text each [`Component`](enum.Component.html) ended in.
+///
+/// ```
+/// # #[macro_use] extern crate maplit;
+/// # fn main() {
+///    use address_formatter::Component::*;
+///
+///    let annotated = address_formatter::FORMATTER
+///        .format_annotated(hashmap!(
+///            City => "Toulouse",
+///            Country => "France",
+///            CountryCode => "FR",
+///            County => "Toulouse",
+///            Postcode => "31000",
+///            Road => "Rue du Médecin-Colonel Calbairac",
+///        ))
+///        .unwrap();
+///
+///    assert_eq!(
+///        annotated.text,
+///        "Rue du Médecin-Colonel Calbairac\n31000 Toulouse\nFrance\n"
+///    );
+///    assert_eq!(&annotated.text[annotated.spans[1].1.clone()], "31000");
+///    assert_eq!(
+///        annotated.spans.iter().map(|(c, _)| *c).collect::<Vec<_>>(),
+///        vec![Road, Postcode, City, Country]
+///    );
+///    // the county (and the country code) are not displayed in France
+///    assert_eq!(annotated.dropped, vec![County, CountryCode]);
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AnnotatedAddress {
+    /// the formatted text, the same as the one given by [`Formatter::format`](struct.Formatter.html#method.format)
+    pub text: String,
+    /// the byte ranges of `text` coming from each component, in the order of the text.
+    ///
+    /// A component can have several spans if its value was split by the formatting
+    pub spans: Vec<(Component, Range<usize>)>,
+    /// the components with a value that do not appear in the text
+    /// (not used by the template, or removed by the cleanup)
+    pub dropped: Vec<Component>,
+}
+
+impl AnnotatedAddress {
+    /// the house number and the road, as written on the road's line of the template
+    /// (without the other components of this line)
+    pub(crate) fn street(&self) -> String {
+        let is_street = |c: &Component| *c == Component::Road || *c == Component::HouseNumber;
+        let road = match self.spans.iter().find(|(c, _)| *c == Component::Road) {
+            Some((_, road)) => road,
+            None => return String::new(),
+        };
+        let line_start = self.text[..road.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.text[road.end..]
+            .find('\n')
+            .map_or(self.text.len(), |i| road.end + i);
+        let on_line = self
+            .spans
+            .iter()
+            .filter(|(c, r)| is_street(c) && line_start <= r.start && r.end <= line_end)
+            .map(|(_, r)| r);
+        let start = on_line.clone().map(|r| r.start).min().unwrap_or(road.start);
+        let end = on_line.map(|r| r.end).max().unwrap_or(road.end);
+        self.text[start..end].to_owned()
+    }
+
+    /// the components in the order of the template, on one line.
+    ///
+    /// The components written on the same line keep their separator (like the space between a postcode and a city),
+    /// the others are separated by ", ".
+    /// A component with the same value as an already written one is skipped (with its separator).
+    pub(crate) fn single_line(&self) -> String {
+        let is_separator = |c: char| c == '\n' || c == ',';
+        let mut line = String::new();
+        let mut written: Vec<&str> = vec![];
+        let mut last = 0;
+        for (_, range) in &self.spans {
+            let between = &self.text[last..range.start];
+            let value = &self.text[range.clone()];
+            last = range.end;
+            if written.contains(&value) {
+                continue;
+            }
+            // the text of the template before the value on its line (like a "CEDEX" or a "〒")
+            let prefix = between.rsplit(is_separator).next().unwrap_or("");
+            if written.is_empty() {
+                line.push_str(prefix.trim_start());
+            } else if between.contains(is_separator) {
+                line.push_str(", ");
+                line.push_str(prefix.trim_start());
+            } else {
+                line.push_str(between);
+            }
+            line.push_str(value);
+            written.push(value);
+        }
+        if let Some(suffix) = self.text[last..].split(is_separator).next() {
+            line.push_str(suffix.trim_end());
+        }
+        line
+    }
+}
+
+/// wrap the values of all the place's components with markers
+pub(crate) fn mark_place(place: &BorrowedPlace) -> BorrowedPlace<'static> {
+    let mut marked = BorrowedPlace::default();
+    for c in Component::iter() {
+        // the blank values are not marked, as they are not shown in the rendered address
+        marked[c] = place[c].as_ref().map(|v| {
+            Cow::Owned(if v.trim().is_empty() {
+                v.to_string()
+            } else {
+                format!("{}{}{}", marker(c), v, COMPONENT_END)
+            })
+        });
+    }
+    marked
+}
+
+fn marker(component: Component) -> char {
+    std::char::from_u32(COMPONENT_START + component as u32).expect("invalid marker")
+}
+
+/// A text, with for each byte the component it comes from
+#[derive(Debug, Default)]
+pub(crate) struct AnnotatedText {
+    text: String,
+    components: Vec<Option<Component>>,
+}
+
+impl AnnotatedText {
+    /// remove the markers of a text rendered with a [`marked place`](fn.mark_place.html)
+    pub fn from_marked(marked_text: &str) -> Self {
+        let mut res = AnnotatedText::default();
+        let mut current = None;
+        for ch in marked_text.chars() {
+            if ch == COMPONENT_END {
+                current = None;
+            } else if let Some(c) = (ch as u32)
+                .checked_sub(COMPONENT_START)
+                .and_then(|i| Component::iter().nth(i as usize))
+            {
+                current = Some(c);
+            } else {
+                res.push_str(ch.encode_utf8(&mut [0; 4]), current);
+            }
+        }
+        res
+    }
+
+    fn push_str(&mut self, s: &str, component: Option<Component>) {
+        self.text.push_str(s);
+        let len = self.components.len() + s.len();
+        self.components.resize(len, component);
+    }
+
+    fn push_slice(&mut self, other: &AnnotatedText, range: Range<usize>) {
+        self.text.push_str(&other.text[range.clone()]);
+        self.components.extend_from_slice(&other.components[range]);
+    }
+
+    /// add a final newline and compute the spans of the components
+    pub fn into_address(mut self, place: &BorrowedPlace) -> AnnotatedAddress {
+        self.push_str("\n", None);
+
+        let mut spans: Vec<(Component, Range<usize>)> = vec![];
+        let mut i = 0;
+        while i < self.text.len() {
+            let start = i;
+            let component = self.component_at(start);
+            while i < self.text.len() && self.component_at(i) == component {
+                i += 1;
+            }
+            if let Some(c) = component {
+                let span = trim_span(&self.text, start..i);
+                if !span.is_empty() {
+                    spans.push((c, span));
+                }
+            }
+        }
+
+        let dropped = Component::iter()
+            .filter(|c| {
+                place[*c]
+                    .as_ref()
+                    .map(|v| !v.trim().is_empty())
+                    .unwrap_or(false)
+            })
+            .filter(|c| spans.iter().all(|(s, _)| s != c))
+            .collect();
+
+        AnnotatedAddress {
+            text: self.text,
+            spans,
+            dropped,
+        }
+    }
+
+    /// the component of a byte, a span never going over a line
+    fn component_at(&self, i: usize) -> Option<Component> {
+        if self.text.as_bytes()[i] == b'\n' {
+            None
+        } else {
+            self.components[i]
+        }
+    }
+}
+
+impl RenderedText for AnnotatedText {
+    /// same as `Regex::replace_all`, the annotations of the text being kept.
+    ///
+    /// If all the annotated bytes of a match come from the same component,
+    /// the replacement is considered to come from this component too
+    fn replace_all(&mut self, regex: &Regex, replacement: &str) {
+        let mut res = AnnotatedText::default();
+        let mut last = 0;
+        let mut replaced = false;
+        for caps in regex.captures_iter(&self.text) {
+            let m = caps.get(0).expect("a capture always has a match");
+            res.push_slice(self, last..m.start());
+            let mut expanded = String::new();
+            caps.expand(replacement, &mut expanded);
+            let component = self.components[m.start()..m.end()]
+                .iter()
+                .filter_map(|c| *c)
+                .dedup()
+                .exactly_one()
+                .ok();
+            res.push_str(&expanded, component);
+            last = m.end();
+            replaced = true;
+        }
+        if !replaced {
+            return;
+        }
+        res.push_slice(self, last..self.text.len());
+        *self = res;
+    }
+
+    /// same as the plain text's dedup, the annotations of the text being kept
+    fn dedup(&mut self) {
+        let mut lines: Vec<AnnotatedText> = vec![];
+        for line in split_ranges(&self.text, 0..self.text.len(), "\n") {
+            let mut new_line = AnnotatedText::default();
+            let tokens = split_ranges(&self.text, line, ", ")
+                .into_iter()
+                .map(|t| trim_range(&self.text, t))
+                .dedup_by(|a, b| self.text[a.clone()] == self.text[b.clone()]);
+            for (i, token) in tokens.enumerate() {
+                if i > 0 {
+                    new_line.push_str(", ", None);
+                }
+                new_line.push_slice(self, token);
+            }
+            if lines
+                .last()
+                .map(|l| l.text != new_line.text)
+                .unwrap_or(true)
+            {
+                lines.push(new_line);
+            }
+        }
+        let mut res = AnnotatedText::default();
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                res.push_str("\n", None);
+            }
+            res.push_slice(line, 0..line.text.len());
+        }
+        *self = res;
+    }
+
+    /// same as `str::trim`, the annotations of the text being kept
+    fn trim(&mut self) {
+        let mut res = AnnotatedText::default();
+        res.push_slice(self, trim_range(&self.text, 0..self.text.len()));
+        *self = res;
+    }
+}
+
+/// same as `str::split`, but giving the byte ranges of the parts
+fn split_ranges(text: &str, range: Range<usize>, separator: &str) -> Vec<Range<usize>> {
+    let mut res = vec![];
+    let mut last = range.start;
+    for (i, _) in text[range.clone()].match_indices(separator) {
+        res.push(last..range.start + i);
+        last = range.start + i + separator.len();
+    }
+    res.push(last..range.end);
+    res
+}
+
+/// same as `str::trim`, but giving the byte range of the trimmed text
+fn trim_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let part = &text[range.clone()];
+    let trimmed = part.trim();
+    if trimmed.is_empty() {
+        range.start..range.start
+    } else {
+        let start = range.start + part.len() - part.trim_start().len();
+        start..start + trimmed.len()
+    }
+}
+
+/// remove the separators (whitespaces and commas) at the boundaries of a span
+fn trim_span(text: &str, range: Range<usize>) -> Range<usize> {
+    let is_separator = |c: char| c.is_whitespace() || c == ',';
+    let part = &text[range.clone()];
+    let trimmed = part.trim_matches(is_separator);
+    if trimmed.is_empty() {
+        range.start..range.start
+    } else {
+        let start = range.start + part.len() - part.trim_start_matches(is_separator).len();
+        start..start + trimmed.len()
+    }
+}
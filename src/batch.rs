@@ -0,0 +1,54 @@
+use crate::{Configuration, Formatter, Place};
+use failure::Error;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+impl Formatter {
+    /// format a batch of places, giving the result of each place in the input order.
+    ///
+    /// With the `rayon` feature, the places are formatted on all the cores.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::Component::*;
+    ///
+    ///    let places = vec![
+    ///        hashmap!(City => "Toulouse", CountryCode => "FR", Road => "Place du Capitole"),
+    ///        hashmap!(City => "Berlin", CountryCode => "DE", Road => "Unter den Linden"),
+    ///    ];
+    ///    let formatted = address_formatter::FORMATTER.format_batch(places, &Default::default());
+    ///
+    ///    assert_eq!(formatted[0].as_ref().unwrap(), "Place du Capitole\nToulouse\n");
+    ///    assert_eq!(formatted[1].as_ref().unwrap(), "Unter den Linden\nBerlin\n");
+    /// # }
+    /// ```
+    pub fn format_batch<P>(
+        &self,
+        places: impl IntoIterator<Item = P>,
+        conf: &Configuration,
+    ) -> Vec<Result<String, Error>>
+    where
+        P: Into<Place> + Send,
+    {
+        let format = |place: P| {
+            let place: Place = place.into();
+            let mut text = String::new();
+            self.format_into(place, conf, &mut text).map(|_| text)
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            places
+                .into_iter()
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .map(format)
+                .collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            places.into_iter().map(format).collect()
+        }
+    }
+}
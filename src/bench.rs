@@ -0,0 +1,53 @@
+//! Access to the stages of the formatting pipeline, for the benchmarks only (not a stable API)
+use crate::formatter::{cleanup_rendered, sanity_clean_place, Rules, Template};
+use crate::{BorrowedPlace, Configuration, CountryCode, Formatter, Place};
+use failure::Error;
+
+/// The country, template and rules used to format a place,
+/// to run each stage of [`Formatter::format_into`](../struct.Formatter.html#method.format_into) separately
+pub struct Stages<'f> {
+    formatter: &'f Formatter,
+    conf: Configuration,
+    country_code: Option<CountryCode>,
+    template: &'f Template,
+    rules: &'f Rules,
+}
+
+impl<'f> Stages<'f> {
+    /// find the country, the template and the rules of a place, like the formatter does
+    pub fn new(formatter: &'f Formatter, place: &Place, conf: Configuration) -> Self {
+        let mut addr = BorrowedPlace::from(place);
+        let country_code = formatter.find_country_code(&mut addr, &conf);
+        sanity_clean_place(&mut addr);
+        let template = formatter.find_template(&addr, &country_code);
+        let rules = formatter.find_rules(&country_code);
+        Stages {
+            formatter,
+            conf,
+            country_code,
+            template,
+            rules,
+        }
+    }
+
+    /// remove the invalid values (like URLs or postcode ranges)
+    pub fn sanity_clean(&self, place: &mut BorrowedPlace) {
+        sanity_clean_place(place)
+    }
+
+    /// apply the replacement rules of the country, and the configuration
+    pub fn preformat(&self, place: &mut BorrowedPlace<'f>) -> Result<(), Error> {
+        self.formatter
+            .preformat_with_config(&self.country_code, self.rules, &self.conf, place)
+    }
+
+    /// render the template at the end of `out`
+    pub fn render(&self, place: &BorrowedPlace, out: &mut String) {
+        self.template.compiled.render(place, out)
+    }
+
+    /// cleanup the rendered text in place
+    pub fn cleanup(&self, text: &mut String, scratch: &mut String) {
+        cleanup_rendered(text, scratch, self.rules)
+    }
+}
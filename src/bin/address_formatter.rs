@@ -0,0 +1,321 @@
+//! Format addresses in batch.
+//!
+//! The addresses are read as JSON Lines (one JSON object per line) or as CSV (with a header),
+//! the keys being the component names or one of their aliases (like `street` or `province`).
+//!
+//! ```text
+//! $ echo '{"road": "Rue du Médecin-Colonel Calbairac", "city": "Toulouse", "country_code": "FR"}' \
+//!     | address-formatter --single-line
+//! Rue du Médecin-Colonel Calbairac, Toulouse
+//! ```
+use address_formatter::{json_value_to_string, Configuration, Formatter, PlaceBuilder};
+use failure::{bail, format_err, Error, ResultExt};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum InputFormat {
+    JsonLines,
+    Csv,
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "jsonl" => Ok(InputFormat::JsonLines),
+            "csv" => Ok(InputFormat::Csv),
+            _ => bail!("unknown input format '{}', expected 'jsonl' or 'csv'", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum OutputFormat {
+    Text,
+    JsonLines,
+    Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => bail!(
+                "unknown output format '{}', expected 'text', 'jsonl' or 'csv'",
+                s
+            ),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "address-formatter",
+    about = "Format addresses read as JSON Lines or CSV"
+)]
+struct Args {
+    /// the files to read, the standard input is read if none is given (or with '-')
+    #[structopt(parse(from_os_str))]
+    inputs: Vec<PathBuf>,
+
+    /// format of the input: 'jsonl' or 'csv'
+    #[structopt(short = "i", long = "input-format", default_value = "jsonl")]
+    input_format: InputFormat,
+
+    /// format of the output: 'text' (the addresses separated by an empty line), 'jsonl' or 'csv'.
+    ///
+    /// The jsonl and csv outputs have an 'id', a 'formatted' and an 'error' field for each input record,
+    /// in the order of the inputs.
+    #[structopt(short = "o", long = "output-format", default_value = "text")]
+    output_format: OutputFormat,
+
+    /// input field written as the 'id' of the jsonl and csv outputs (and not used as an address component).
+    /// The location of the record (like 'addresses.csv:2') is written if not given
+    #[structopt(long = "id-field")]
+    id_field: Option<String>,
+
+    /// force the country of all the addresses (ISO 3166-1 alpha-2 code)
+    #[structopt(short = "c", long = "country")]
+    country: Option<String>,
+
+    /// abbreviate the formatted addresses (like "Avenue" to "Av.")
+    #[structopt(short = "a", long = "abbreviate")]
+    abbreviate: bool,
+
+    /// write each address on only one line
+    #[structopt(short = "s", long = "single-line")]
+    single_line: bool,
+
+    /// directory of the opencage configuration, the embedded configuration is used if not given
+    #[structopt(long = "config-dir", parse(from_os_str))]
+    config_dir: Option<PathBuf>,
+}
+
+/// an address read from an input, with its location (like `addresses.csv:2`) for the error messages
+struct Record {
+    location: String,
+    values: Result<Vec<(String, String)>, Error>,
+}
+
+fn read_json_lines(
+    name: &str,
+    reader: impl BufRead,
+    mut handle: impl FnMut(Record) -> Result<(), Error>,
+) -> Result<(), Error> {
+    for (i, line) in reader.lines().enumerate() {
+        let location = format!("{}:{}", name, i + 1);
+        let line = line.with_context(|_| format!("impossible to read {}", location))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let values = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&line)
+            .map(|object| {
+                object
+                    .into_iter()
+                    .filter_map(|(k, v)| json_value_to_string(v).map(|v| (k, v)))
+                    .collect()
+            })
+            .map_err(|e| format_err!("{}: invalid JSON object: {}", location, e));
+        handle(Record { location, values })?;
+    }
+    Ok(())
+}
+
+fn read_csv(
+    name: &str,
+    reader: impl BufRead,
+    mut handle: impl FnMut(Record) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .with_context(|_| format!("impossible to read the header of {}", name))?
+        .clone();
+    for (i, record) in reader.records().enumerate() {
+        // the header is the first line
+        let location = format!("{}:{}", name, i + 2);
+        let values = record
+            .map(|r| {
+                headers
+                    .iter()
+                    .zip(r.iter())
+                    .filter(|(_, v)| !v.is_empty())
+                    .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                    .collect()
+            })
+            .map_err(|e| format_err!("{}: invalid CSV record: {}", location, e));
+        handle(Record { location, values })?;
+    }
+    Ok(())
+}
+
+enum Output<W: Write> {
+    Text {
+        writer: W,
+        /// the multi-line addresses are separated by an empty line
+        separated: bool,
+        first: bool,
+    },
+    JsonLines(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> Output<W> {
+    fn new(format: OutputFormat, single_line: bool, writer: W) -> Result<Self, Error> {
+        Ok(match format {
+            OutputFormat::Text => Output::Text {
+                writer,
+                separated: !single_line,
+                first: true,
+            },
+            OutputFormat::JsonLines => Output::JsonLines(writer),
+            OutputFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(["id", "formatted", "error"])?;
+                Output::Csv(Box::new(writer))
+            }
+        })
+    }
+
+    /// write the result of a record, an address that could not be formatted being written
+    /// as an empty text or with its error (so the output stays aligned with the input)
+    fn write(&mut self, id: &str, formatted: &Result<String, Error>) -> Result<(), Error> {
+        match self {
+            Output::Text {
+                writer,
+                separated,
+                first,
+            } => {
+                if *separated && !*first {
+                    writeln!(writer)?;
+                }
+                *first = false;
+                let text = formatted.as_ref().map(|f| f.as_str()).unwrap_or("");
+                writeln!(writer, "{}", text.trim_end_matches('\n'))?
+            }
+            Output::JsonLines(w) => {
+                let line = match formatted {
+                    Ok(f) => serde_json::json!({ "id": id, "formatted": f.trim_end_matches('\n') }),
+                    Err(e) => {
+                        serde_json::json!({ "id": id, "formatted": null, "error": e.to_string() })
+                    }
+                };
+                writeln!(w, "{}", line)?
+            }
+            Output::Csv(w) => match formatted {
+                Ok(f) => w.write_record([id, f.trim_end_matches('\n'), ""])?,
+                Err(e) => w.write_record([id, "", &e.to_string()])?,
+            },
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        match self {
+            Output::Text { writer: w, .. } | Output::JsonLines(w) => w.flush()?,
+            Output::Csv(w) => w.flush()?,
+        }
+        Ok(())
+    }
+}
+
+fn run(args: Args) -> Result<usize, Error> {
+    let (formatter, place_builder) = match &args.config_dir {
+        Some(dir) => (
+            Formatter::from_config_dir(dir)?,
+            PlaceBuilder::from_config_dir(dir)?,
+        ),
+        None => (Formatter::try_new()?, PlaceBuilder::try_new()?),
+    };
+    let conf = Configuration {
+        country_code: args.country.clone(),
+        abbreviate: Some(args.abbreviate),
+        ..Default::default()
+    };
+
+    let stdout = std::io::stdout();
+    let mut output = Output::new(args.output_format, args.single_line, stdout.lock())?;
+    let mut nb_errors = 0;
+    let mut handle = |record: Record| -> Result<(), Error> {
+        let id_field = args.id_field.as_deref();
+        let id = match (id_field, &record.values) {
+            (Some(field), Ok(values)) => values
+                .iter()
+                .find(|(k, _)| k == field)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default(),
+            (Some(_), Err(_)) => String::new(),
+            (None, _) => record.location.clone(),
+        };
+        let location = &record.location;
+        let formatted = record.values.and_then(|values| {
+            let place = place_builder.build_place(
+                values
+                    .iter()
+                    .filter(|(k, _)| Some(k.as_str()) != id_field)
+                    .map(|(k, v)| (k.as_str(), v.to_owned())),
+            );
+            if args.single_line {
+                formatter.format_single_line(place, conf.clone())
+            } else {
+                formatter
+                    .format_lines_with_config(place, conf.clone())
+                    .map(|f| f.to_string())
+            }
+            .map_err(|e| format_err!("{}: impossible to format the address: {}", location, e))
+        });
+        if let Err(e) = &formatted {
+            // an invalid address does not stop the processing, the error is reported
+            eprintln!("{}", e);
+            nb_errors += 1;
+        }
+        output.write(&id, &formatted)
+    };
+
+    let inputs = if args.inputs.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.inputs.clone()
+    };
+    for input in &inputs {
+        let (name, reader): (String, Box<dyn BufRead>) = if input.to_str() == Some("-") {
+            (
+                "<stdin>".to_owned(),
+                Box::new(std::io::BufReader::new(std::io::stdin())),
+            )
+        } else {
+            let file = std::fs::File::open(input)
+                .with_context(|_| format!("impossible to open {}", input.display()))?;
+            (
+                input.display().to_string(),
+                Box::new(std::io::BufReader::new(file)),
+            )
+        };
+        match args.input_format {
+            InputFormat::JsonLines => read_json_lines(&name, reader, &mut handle)?,
+            InputFormat::Csv => read_csv(&name, reader, &mut handle)?,
+        }
+    }
+    output.flush()?;
+    Ok(nb_errors)
+}
+
+fn main() {
+    env_logger::init();
+    match run(Args::from_args()) {
+        Ok(0) => {}
+        Ok(nb_errors) => {
+            eprintln!("{} address(es) could not be formatted", nb_errors);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
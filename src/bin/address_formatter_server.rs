@@ -0,0 +1,276 @@
+//! HTTP/JSON service formatting addresses.
+//!
+//! Endpoints:
+//!  * `GET /health`: `{"status": "ok"}`
+//!  * `POST /format`: format a JSON object keyed by component names (or their aliases),
+//!    gives `{"formatted": "<the address>"}`
+//!  * `POST /format/batch`: format a JSON array of objects,
+//!    gives `{"results": [{"formatted": "..."}, {"error": "..."}, ...]}` in the same order
+//!  * `POST /place`: gives the [`Place`](../address_formatter/struct.Place.html) built from a JSON object
+//!
+//! A body larger than `--max-body-size` is rejected with a `413 Payload Too Large` status.
+//!
+//! The fields of the [`Configuration`](../address_formatter/struct.Configuration.html)
+//! (`country_code`, `abbreviate`, `language` and `origin_country`) can be given as query parameters.
+//!
+//! ```text
+//! $ curl -X POST 'localhost:8080/format?abbreviate=true' \
+//!     -d '{"road": "Avenue de la République", "city": "Paris", "postcode": "75011", "country_code": "FR"}'
+//! {"formatted":"Av de la République\n75011 Paris\n"}
+//! ```
+use address_formatter::{
+    json_value_to_string, Component, Configuration, Formatter, Place, PlaceBuilder,
+};
+use failure::{bail, format_err, Error};
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use structopt::StructOpt;
+use strum::IntoEnumIterator;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "address-formatter-server",
+    about = "HTTP service formatting addresses"
+)]
+struct Args {
+    /// address to listen on
+    #[structopt(short = "b", long = "bind", default_value = "127.0.0.1:8080")]
+    bind: String,
+
+    /// number of threads handling the requests
+    #[structopt(short = "w", long = "workers", default_value = "4")]
+    workers: usize,
+
+    /// directory of the opencage configuration, the embedded configuration is used if not given
+    #[structopt(long = "config-dir", parse(from_os_str))]
+    config_dir: Option<PathBuf>,
+
+    /// maximum size of a request body, in bytes
+    #[structopt(long = "max-body-size", default_value = "10485760")]
+    max_body_size: u64,
+}
+
+struct Service {
+    formatter: Formatter,
+    place_builder: PlaceBuilder,
+    max_body_size: u64,
+}
+
+/// an HTTP error, with its status code
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn bad_request(e: impl std::fmt::Display) -> Self {
+        HttpError {
+            status: 400,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// read the [`Configuration`](struct.Configuration.html) from the query parameters
+fn read_configuration(query: &str) -> Result<Configuration, Error> {
+    let mut conf = Configuration::default();
+    for (k, v) in form_urlencoded::parse(query.as_bytes()) {
+        match k.as_ref() {
+            "country_code" => conf.country_code = Some(v.into_owned()),
+            "abbreviate" => {
+                conf.abbreviate = Some(
+                    v.parse()
+                        .map_err(|_| format_err!("invalid boolean '{}' for 'abbreviate'", v))?,
+                )
+            }
+            "language" => conf.language = Some(v.into_owned()),
+            "origin_country" => conf.origin_country = Some(v.into_owned()),
+            _ => bail!("unknown parameter '{}'", k),
+        }
+    }
+    Ok(conf)
+}
+
+fn place_to_json(place: &Place) -> Value {
+    Value::Object(
+        Component::iter()
+            .filter_map(|c| {
+                place[c]
+                    .as_ref()
+                    .filter(|v| !v.is_empty())
+                    .map(|v| (c.to_string(), Value::String(v.clone())))
+            })
+            .collect(),
+    )
+}
+
+impl Service {
+    fn build_place(&self, value: Value) -> Result<Place, Error> {
+        match value {
+            Value::Object(object) => {
+                let values: Vec<(String, String)> = object
+                    .into_iter()
+                    .filter_map(|(k, v)| json_value_to_string(v).map(|v| (k, v)))
+                    .collect();
+                Ok(self
+                    .place_builder
+                    .build_place(values.iter().map(|(k, v)| (k.as_str(), v.clone()))))
+            }
+            _ => bail!("an address should be a JSON object"),
+        }
+    }
+
+    fn format(&self, value: Value, conf: &Configuration) -> Result<Value, Error> {
+        let place = self.build_place(value)?;
+        let formatted = self.formatter.format_with_config(place, conf.clone())?;
+        Ok(json!({ "formatted": formatted }))
+    }
+
+    fn handle(
+        &self,
+        method: &Method,
+        path: &str,
+        query: &str,
+        body: &str,
+    ) -> Result<Value, HttpError> {
+        let parse_body = || serde_json::from_str::<Value>(body).map_err(HttpError::bad_request);
+        match (method, path) {
+            (Method::Get, "/health") => Ok(json!({ "status": "ok" })),
+            (Method::Post, "/format") => {
+                let conf = read_configuration(query).map_err(HttpError::bad_request)?;
+                self.format(parse_body()?, &conf)
+                    .map_err(HttpError::bad_request)
+            }
+            (Method::Post, "/format/batch") => {
+                let conf = read_configuration(query).map_err(HttpError::bad_request)?;
+                match parse_body()? {
+                    Value::Array(values) => {
+                        // an invalid address does not fail the whole batch
+                        let results: Vec<Value> = values
+                            .into_iter()
+                            .map(|v| {
+                                self.format(v, &conf)
+                                    .unwrap_or_else(|e| json!({ "error": e.to_string() }))
+                            })
+                            .collect();
+                        Ok(json!({ "results": results }))
+                    }
+                    _ => Err(HttpError::bad_request("a batch should be a JSON array")),
+                }
+            }
+            (Method::Post, "/place") => self
+                .build_place(parse_body()?)
+                .map(|p| place_to_json(&p))
+                .map_err(HttpError::bad_request),
+            (_, "/health") | (_, "/format") | (_, "/format/batch") | (_, "/place") => {
+                Err(HttpError {
+                    status: 405,
+                    message: format!("method {} not allowed", method),
+                })
+            }
+            _ => Err(HttpError {
+                status: 404,
+                message: format!("unknown endpoint {}", path),
+            }),
+        }
+    }
+
+    /// read the body, without reading more than the maximum size
+    fn read_body(&self, request: &mut Request) -> Result<String, HttpError> {
+        let too_large = || HttpError {
+            status: 413,
+            message: format!(
+                "the body is larger than the limit of {} bytes",
+                self.max_body_size
+            ),
+        };
+        if request
+            .body_length()
+            .is_some_and(|l| l as u64 > self.max_body_size)
+        {
+            return Err(too_large());
+        }
+        let mut body = String::new();
+        request
+            .as_reader()
+            .take(self.max_body_size + 1)
+            .read_to_string(&mut body)
+            .map_err(|e| HttpError::bad_request(format!("invalid body: {}", e)))?;
+        if body.len() as u64 > self.max_body_size {
+            return Err(too_large());
+        }
+        Ok(body)
+    }
+
+    fn respond(&self, mut request: Request) {
+        let (status, value) = match self.read_body(&mut request) {
+            Ok(body) => {
+                let url = request.url().to_owned();
+                let (path, query) = match url.find('?') {
+                    Some(i) => (&url[..i], &url[i + 1..]),
+                    None => (url.as_str(), ""),
+                };
+                match self.handle(request.method(), path, query, &body) {
+                    Ok(v) => (200, v),
+                    Err(e) => (e.status, json!({ "error": e.message })),
+                }
+            }
+            Err(e) => (e.status, json!({ "error": e.message })),
+        };
+        let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect("invalid header");
+        let response = Response::from_string(value.to_string())
+            .with_status_code(status)
+            .with_header(header);
+        if let Err(e) = request.respond(response) {
+            log::warn!("impossible to send the response: {}", e);
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    let service = Arc::new(match &args.config_dir {
+        Some(dir) => Service {
+            formatter: Formatter::from_config_dir(dir)?,
+            place_builder: PlaceBuilder::from_config_dir(dir)?,
+            max_body_size: args.max_body_size,
+        },
+        None => Service {
+            formatter: Formatter::try_new()?,
+            place_builder: PlaceBuilder::try_new()?,
+            max_body_size: args.max_body_size,
+        },
+    });
+    let server = Arc::new(Server::http(&args.bind).map_err(|e| format_err!("{}", e))?);
+    // the actual address is written so the port can be known when binding on the port 0
+    println!("listening on {}", server.server_addr());
+
+    let workers: Vec<_> = (0..args.workers.max(1))
+        .map(|_| {
+            let server = server.clone();
+            let service = service.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    service.respond(request);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| format_err!("a worker thread panicked"))?;
+    }
+    Ok(())
+}
+
+fn main() {
+    env_logger::init();
+    if let Err(e) = run(Args::from_args()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
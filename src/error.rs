@@ -0,0 +1,54 @@
+use failure::Fail;
+
+/// A problem found in the opencage configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationIssue {
+    /// the configuration file, relative to the configuration directory (like `countries/worldwide.yaml`),
+    /// or `FormatterBuilder` for the rules added with a [`FormatterBuilder`](struct.FormatterBuilder.html)
+    pub file: String,
+    /// the country concerned by the problem, if any
+    pub country: Option<String>,
+    /// the rule that failed (like `address_template` or `replace: ^Borough of `), if any
+    pub rule: Option<String>,
+    /// description of the problem
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigurationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(country) = &self.country {
+            write!(f, ", country {}", country)?;
+        }
+        if let Some(rule) = &self.rule {
+            write!(f, ", rule '{}'", rule)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Error returned when the opencage configuration cannot be loaded
+///
+/// The configuration is fully read before returning this error,
+/// so it lists all the problems found, not only the first one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigurationError {
+    /// all the problems found in the configuration
+    pub issues: Vec<ConfigurationIssue>,
+}
+
+impl std::fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid configuration, {} problem(s) found",
+            self.issues.len()
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl Fail for ConfigurationError {}
@@ -0,0 +1,249 @@
+//! C API of the formatter, to use it from other languages.
+//!
+//! The declarations are in the `include/address_formatter.h` header.
+//!
+//! All the strings are nul terminated UTF-8 strings. The strings returned by the library
+//! must be freed with [`address_formatter_string_free`](fn.address_formatter_string_free.html).
+//! When a function fails, it returns a null pointer and the reason can be read with
+//! [`address_formatter_last_error`](fn.address_formatter_last_error.html).
+use crate::{Configuration, Formatter, Place, PlaceBuilder};
+use failure::{format_err, Error};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// A formatter and the place builder using the same configuration
+pub struct AddressFormatter {
+    formatter: Formatter,
+    place_builder: PlaceBuilder,
+}
+
+/// A place to format
+pub struct AddressFormatterPlace {
+    place: Place,
+}
+
+/// The formatting options, the C equivalent of the [`Configuration`](../struct.Configuration.html).
+///
+/// The null strings are considered as not set.
+#[repr(C)]
+pub struct AddressFormatterOptions {
+    /// force the use of a given country
+    pub country_code: *const c_char,
+    /// use the abbreviations if not 0
+    pub abbreviate: c_int,
+    /// force the use of a given language for the abbreviations
+    pub language: *const c_char,
+    /// country code of the sender, the country is not written for domestic mail
+    pub origin_country: *const c_char,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(e: impl std::fmt::Display) {
+    let message = CString::new(e.to_string().replace('\0', ""))
+        .expect("the nul characters have been removed");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// run a function, returning a null pointer and storing the error (or the panic) if it fails
+fn wrap<T>(f: impl FnOnce() -> Result<T, Error>) -> *mut T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(v)) => Box::into_raw(Box::new(v)),
+        Ok(Err(e)) => {
+            set_last_error(e);
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("the address formatter panicked");
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn read_str<'a>(s: *const c_char, name: &str) -> Result<Option<&'a str>, Error> {
+    if s.is_null() {
+        Ok(None)
+    } else {
+        CStr::from_ptr(s)
+            .to_str()
+            .map(Some)
+            .map_err(|e| format_err!("{} is not a valid UTF-8 string: {}", name, e))
+    }
+}
+
+unsafe fn read_options(options: *const AddressFormatterOptions) -> Result<Configuration, Error> {
+    let options = match options.as_ref() {
+        Some(o) => o,
+        None => return Ok(Configuration::default()),
+    };
+    let to_owned = |s: Option<&str>| s.map(|s| s.to_owned());
+    Ok(Configuration {
+        country_code: to_owned(read_str(options.country_code, "country_code")?),
+        abbreviate: Some(options.abbreviate != 0),
+        language: to_owned(read_str(options.language, "language")?),
+        origin_country: to_owned(read_str(options.origin_country, "origin_country")?),
+    })
+}
+
+/// Get the message of the last error that occurred in the current thread,
+/// or a null pointer if there was none.
+///
+/// The message is owned by the library and is valid until the next failing call in the same thread.
+#[no_mangle]
+pub extern "C" fn address_formatter_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|e| e.as_ptr())
+            .unwrap_or(ptr::null())
+    })
+}
+
+/// Create a formatter with the opencage configuration embedded in the library.
+///
+/// Returns null if the configuration is invalid. The formatter must be freed with
+/// [`address_formatter_free`](fn.address_formatter_free.html).
+#[no_mangle]
+pub extern "C" fn address_formatter_new() -> *mut AddressFormatter {
+    wrap(|| {
+        Ok(AddressFormatter {
+            formatter: Formatter::try_new()?,
+            place_builder: PlaceBuilder::try_new()?,
+        })
+    })
+}
+
+/// Create a formatter with the opencage configuration read from a directory.
+///
+/// Returns null if the configuration cannot be read. The formatter must be freed with
+/// [`address_formatter_free`](fn.address_formatter_free.html).
+///
+/// # Safety
+///
+/// `config_dir` must be a valid nul terminated string
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_from_config_dir(
+    config_dir: *const c_char,
+) -> *mut AddressFormatter {
+    wrap(|| {
+        let dir =
+            read_str(config_dir, "config_dir")?.ok_or_else(|| format_err!("config_dir is null"))?;
+        Ok(AddressFormatter {
+            formatter: Formatter::try_from_config_dir(dir)?,
+            place_builder: PlaceBuilder::try_from_config_dir(dir)?,
+        })
+    })
+}
+
+/// Free a formatter.
+///
+/// # Safety
+///
+/// `formatter` must have been created by this library (or be null), and must not be used afterward
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_free(formatter: *mut AddressFormatter) {
+    if !formatter.is_null() {
+        drop(Box::from_raw(formatter));
+    }
+}
+
+/// Build a place from `len` keys and values, the keys being the component names
+/// (like `road` or `country_code`) or one of their aliases (like `street`).
+///
+/// Returns null if a key or a value is not a valid string. The place must be freed with
+/// [`address_formatter_place_free`](fn.address_formatter_place_free.html).
+///
+/// # Safety
+///
+/// `formatter` must be a valid formatter, `keys` and `values` must be arrays of `len`
+/// valid nul terminated strings (the null values are ignored)
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_place_new(
+    formatter: *const AddressFormatter,
+    keys: *const *const c_char,
+    values: *const *const c_char,
+    len: usize,
+) -> *mut AddressFormatterPlace {
+    wrap(|| {
+        let formatter = formatter
+            .as_ref()
+            .ok_or_else(|| format_err!("the formatter is null"))?;
+        if len > 0 && (keys.is_null() || values.is_null()) {
+            return Err(format_err!("the keys or the values are null"));
+        }
+        let mut fields = vec![];
+        for i in 0..len {
+            let key =
+                read_str(*keys.add(i), "key")?.ok_or_else(|| format_err!("key {} is null", i))?;
+            if let Some(value) = read_str(*values.add(i), key)? {
+                fields.push((key, value.to_owned()));
+            }
+        }
+        Ok(AddressFormatterPlace {
+            place: formatter.place_builder.build_place(fields),
+        })
+    })
+}
+
+/// Free a place.
+///
+/// # Safety
+///
+/// `place` must have been created by this library (or be null), and must not be used afterward
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_place_free(place: *mut AddressFormatterPlace) {
+    if !place.is_null() {
+        drop(Box::from_raw(place));
+    }
+}
+
+/// Format a place, the options can be null to use the default ones.
+///
+/// Returns null if the place cannot be formatted. The returned string must be freed with
+/// [`address_formatter_string_free`](fn.address_formatter_string_free.html).
+///
+/// # Safety
+///
+/// `formatter` and `place` must be valid, and `options` must be null or valid
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_format(
+    formatter: *const AddressFormatter,
+    place: *const AddressFormatterPlace,
+    options: *const AddressFormatterOptions,
+) -> *mut c_char {
+    let formatted = wrap(|| {
+        let formatter = formatter
+            .as_ref()
+            .ok_or_else(|| format_err!("the formatter is null"))?;
+        let place = place
+            .as_ref()
+            .ok_or_else(|| format_err!("the place is null"))?;
+        let conf = read_options(options)?;
+        let formatted = formatter
+            .formatter
+            .format_with_config(place.place.clone(), conf)?;
+        Ok(CString::new(formatted.replace('\0', ""))?)
+    });
+    if formatted.is_null() {
+        ptr::null_mut()
+    } else {
+        Box::from_raw(formatted).into_raw()
+    }
+}
+
+/// Free a string returned by the library.
+///
+/// # Safety
+///
+/// `s` must have been returned by this library (or be null), and must not be used afterward
+#[no_mangle]
+pub unsafe extern "C" fn address_formatter_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,72 @@
+/// A [`Place`](struct.Place.html) formatted by the [`Formatter`](struct.Formatter.html), as a list of lines
+///
+/// ```
+/// # #[macro_use] extern crate maplit;
+/// # fn main() {
+///    use address_formatter::Component::*;
+///
+///    let formatted = address_formatter::FORMATTER
+///        .format_lines(hashmap!(
+///            City => "Toulouse",
+///            Country => "France",
+///            CountryCode => "FR",
+///            HouseNumber => "17",
+///            Postcode => "31000",
+///            Road => "Rue du Médecin-Colonel Calbairac",
+///        ))
+///        .unwrap();
+///
+///    assert_eq!(
+///        formatted.lines(),
+///        ["17 Rue du Médecin-Colonel Calbairac", "31000 Toulouse", "France"]
+///    );
+///    assert_eq!(
+///        formatted.single_line(", "),
+///        "17 Rue du Médecin-Colonel Calbairac, 31000 Toulouse, France"
+///    );
+///    // the `Display` implementation gives the same text as `Formatter::format`
+///    assert_eq!(
+///        formatted.to_string(),
+///        "17 Rue du Médecin-Colonel Calbairac\n31000 Toulouse\nFrance\n"
+///    );
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FormattedAddress {
+    lines: Vec<String>,
+}
+
+impl FormattedAddress {
+    /// build the address from the cleaned up rendered text (ending with a newline)
+    pub(crate) fn from_text(text: &str) -> Self {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        let lines = if text.is_empty() {
+            vec![]
+        } else {
+            text.split('\n').map(|l| l.to_owned()).collect()
+        };
+        FormattedAddress { lines }
+    }
+
+    /// the lines of the address
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// the lines of the address, consuming it
+    pub fn into_lines(self) -> Vec<String> {
+        self.lines
+    }
+
+    /// the address on only one line, the lines being joined with a separator
+    pub fn single_line(&self, separator: &str) -> String {
+        self.lines.join(separator)
+    }
+}
+
+impl std::fmt::Display for FormattedAddress {
+    /// the lines of the address, each one ending with a newline
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "{}", self.lines.join("\n"))
+    }
+}
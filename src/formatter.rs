@@ -1,14 +1,20 @@
-use crate::{Component, Place};
-use failure::Fail;
+use crate::annotated_address::{mark_place, AnnotatedText};
+use crate::error::ConfigurationError;
+use crate::read_configuration::FormatterFiles;
+use crate::template_engine::CompiledTemplate;
+use crate::{AnnotatedAddress, BorrowedPlace, Component, FormattedAddress, Place};
 use failure::{format_err, Error};
 use itertools::Itertools;
 use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use strum::IntoEnumIterator;
 
-const TEMPLATE_NAME: &'static str = "addr";
-
 /// Represents a Regex and the value to replace the regex matches with
 #[derive(Debug, Clone)]
 pub(crate) struct Replacement {
@@ -24,18 +30,29 @@ pub(crate) enum ReplaceRule {
     Component((Component, Replacement)),
 }
 
-#[derive(Debug, Hash, Eq, PartialEq, Clone)]
-pub struct CountryCode(String); // TODO small string
+/// An [ISO 3166-1 alpha-2](https://en.wikipedia.org/wiki/ISO_3166-1_alpha-2) country code
+///
+/// ```
+/// use address_formatter::CountryCode;
+/// let country_code: CountryCode = "fr".parse().unwrap();
+/// assert_eq!(country_code.as_str(), "FR");
+/// ```
+#[derive(Hash, Eq, PartialEq, Clone)]
+pub struct CountryCode([u8; 2]);
 
 impl FromStr for CountryCode {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.len() == 2 {
-            if s == "UK" {
-                Ok(CountryCode("GB".to_owned()))
+            if s.eq_ignore_ascii_case("UK") {
+                Ok(CountryCode(*b"GB"))
             } else {
-                Ok(CountryCode(s.to_uppercase()))
+                let code = s.as_bytes();
+                Ok(CountryCode([
+                    code[0].to_ascii_uppercase(),
+                    code[1].to_ascii_uppercase(),
+                ]))
             }
         } else {
             Err(format_err!(
@@ -47,14 +64,22 @@ impl FromStr for CountryCode {
 }
 
 impl CountryCode {
+    /// the upper case country code
     pub fn as_str(&self) -> &str {
-        self.0.as_str()
+        // only the ascii letters are changed, so the code is still valid utf-8
+        std::str::from_utf8(&self.0).expect("a country code is valid utf-8")
+    }
+}
+
+impl std::fmt::Debug for CountryCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("CountryCode").field(&self.as_str()).finish()
     }
 }
 
 impl std::fmt::Display for CountryCode {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -65,36 +90,28 @@ pub(crate) struct NewComponent {
     pub new_value: String,
 }
 
-/// The template handle the handlerbar template used to format a [`Place`](struct.Place.html)
-#[derive(Debug, Default)]
+/// The template used to format a [`Place`](struct.Place.html)
+///
+/// The compiled template is shared, so the countries using the same template (like the
+/// `use_country` ones) do not compile their own
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Template {
-    /// Moustache template
-    place_template: String, // used only to clone the template
-    pub handlebar_handler: handlebars::Handlebars,
+    pub compiled: Arc<CompiledTemplate>,
 }
 
 impl Template {
-    pub fn new(place_template: &str) -> Self {
-        let mut template_engine = crate::handlebar_helper::new_template_engine();
-        template_engine
-            .register_template_string(TEMPLATE_NAME, place_template)
-            .expect("impossible to build template");
-        Template {
-            place_template: place_template.to_owned(),
-            handlebar_handler: template_engine,
-        }
-    }
-}
-
-impl Clone for Template {
-    fn clone(&self) -> Self {
-        Self::new(self.place_template.as_str())
+    pub fn new(place_template: &str) -> Result<Self, Error> {
+        let compiled = CompiledTemplate::compile(place_template)
+            .map_err(|e| format_err!("impossible to build template: {}", e))?;
+        Ok(Template {
+            compiled: Arc::new(compiled),
+        })
     }
 }
 
 /// The `Rules` contains all the rules used to cleanup the placees
 /// Some of those rules are used as preformating rules (before changing the [`Place`](struct.Place.html)
-/// to a text with the handlebar template)
+/// to a text with the template)
 /// And some of those rules are used as postformating rules, on the formatted text
 #[derive(Debug, Default, Clone)]
 pub(crate) struct Rules {
@@ -122,6 +139,9 @@ pub(crate) struct Templates {
 /// The main method is the `format` method, that takes a [`Place`](struct.Place.html)
 /// or something that can be converted to a [`Place`](struct.Place.html) and return a result with the formatted `String`
 ///
+/// The `Formatter` is `Send` and `Sync`, so it can be shared between threads
+/// (like the [`FORMATTER`](struct.FORMATTER.html) singleton, or by [`format_batch`](struct.Formatter.html#method.format_batch)).
+///
 /// ```
 /// # #[macro_use] extern crate maplit;
 /// # fn main() {
@@ -154,30 +174,128 @@ pub(crate) struct Templates {
 /// ```
 pub struct Formatter {
     pub(crate) templates: Templates,
-    pub(crate) county_codes: HashMap<(CountryCode, String), String>,
-    pub(crate) state_codes: HashMap<(CountryCode, String), String>,
-    // country_to_lang: Vec<>,
-    // abbreviations: Vec<>,
+    /// the county codes by country and county name
+    pub(crate) county_codes: HashMap<CountryCode, HashMap<String, String>>,
+    /// the state codes by country and state name
+    pub(crate) state_codes: HashMap<CountryCode, HashMap<String, String>>,
+    /// languages spoken in a country (from 'country2lang.yaml')
+    pub(crate) country_to_lang: HashMap<CountryCode, Vec<String>>,
+    /// abbreviation rules by language (from 'abbreviations/<lang>.yaml')
+    pub(crate) abbreviations: HashMap<String, Vec<ReplaceRule>>,
     // valid_replacement_components: Vec<>
 }
 
+// the formatter (and its compiled templates) must stay shareable between threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Formatter>();
+    assert_send_sync::<Template>();
+    assert_send_sync::<PlaceBuilder>();
+};
+
+/// Administrative depth of the labels made by [`Formatter::format_short`](struct.Formatter.html#method.format_short)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdminDepth {
+    /// only the road
+    Street,
+    /// the road and the locality (city, town or village)
+    Locality,
+    /// the road, the locality and the county and state
+    State,
+    /// the road, the locality, the county and state and the country
+    Country,
+}
+
+impl AdminDepth {
+    /// the components kept for this depth
+    fn components(self) -> &'static [Component] {
+        use Component::*;
+        const COMPONENTS: [Component; 10] = [
+            Road,
+            Village,
+            Town,
+            City,
+            County,
+            StateDistrict,
+            State,
+            StateCode,
+            Island,
+            Country,
+        ];
+        match self {
+            AdminDepth::Street => &COMPONENTS[..1],
+            AdminDepth::Locality => &COMPONENTS[..4],
+            AdminDepth::State => &COMPONENTS[..9],
+            AdminDepth::Country => &COMPONENTS[..],
+        }
+    }
+}
+
 /// This configuration changes the [`Formatter`](struct.Formatter.html) behavior
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct Configuration {
     /// force the use of a give country (so the [`Place`](struct.Place.html) country_code is not used)
     pub country_code: Option<String>,
     /// use abbreviation in the formated text (like "Avenue" to "Av.")
+    ///
+    /// The abbreviations used are the ones of the languages spoken in the place's country
+    /// (or the ones of `language` if it is set)
     pub abbreviate: Option<bool>,
+    /// force the use of a given language (ISO 639-1 code like "fr" or "nl"),
+    /// instead of all the languages spoken in the country.
+    /// This is useful for multilingual countries (like Belgium, Canada or Switzerland)
+    pub language: Option<String>,
+    /// country code of the sender, to format the place as a postal address.
+    ///
+    /// If the place is in the same country (after the territories have been attached to their country,
+    /// like Guadeloupe to France), the country is not written, as for domestic mail.
+    /// Else the country is written in capital letters, as recommended for international mail.
+    ///
+    /// An invalid country code makes the formatting fail.
+    pub origin_country: Option<String>,
 }
 
 impl Default for Formatter {
-    /// Default constructor
+    /// Default constructor, with the opencage configuration embedded in the binary
     fn default() -> Self {
-        crate::read_configuration::read_configuration()
+        Self::try_new().unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl Formatter {
+    /// Build a [`Formatter`](struct.Formatter.html) with the opencage configuration embedded in the binary.
+    ///
+    /// Same as [`default`](struct.Formatter.html#method.default), but returns an error instead of panicking
+    /// if the configuration is invalid
+    pub fn try_new() -> Result<Self, ConfigurationError> {
+        crate::read_configuration::read_configuration(&FormatterFiles::embedded())
+    }
+
+    /// Build a [`Formatter`](struct.Formatter.html) with the opencage configuration read at runtime
+    /// from a directory (the `conf` directory of the [opencage repository](https://github.com/OpenCageData/address-formatting)).
+    ///
+    /// This makes it possible to use up to date configuration without rebuilding the crate.
+    ///
+    /// ```
+    /// let formatter = address_formatter::Formatter::from_config_dir("./address-formatting/conf").unwrap();
+    /// # assert!(address_formatter::Formatter::from_config_dir("./not_a_dir").is_err());
+    /// ```
+    pub fn from_config_dir(config_dir: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::try_from_config_dir(config_dir)?)
+    }
+
+    /// Same as [`from_config_dir`](struct.Formatter.html#method.from_config_dir), but with a
+    /// [`ConfigurationError`](struct.ConfigurationError.html) listing all the problems found in the configuration
+    ///
+    /// ```
+    /// let error = address_formatter::Formatter::try_from_config_dir("./not_a_dir").err().unwrap();
+    /// assert_eq!(error.issues.len(), 5); // all the missing files are reported
+    /// ```
+    pub fn try_from_config_dir(config_dir: impl AsRef<Path>) -> Result<Self, ConfigurationError> {
+        let files = FormatterFiles::from_dir(config_dir.as_ref())?;
+        crate::read_configuration::read_configuration(&files)
+    }
+
     /// make a human readable text from a [`Place`](struct.Place.html)
     /// ```
     /// # #[macro_use] extern crate maplit;
@@ -212,6 +330,23 @@ impl Formatter {
         self.format_with_config(into_addr.into(), Configuration::default())
     }
 
+    /// Give the languages (ISO 639-1 codes) spoken in a country
+    ///
+    /// The languages are given in the order of the opencage `country2lang.yaml` file.
+    /// An unknown country has no languages.
+    ///
+    /// ```
+    /// let formatter = address_formatter::Formatter::default();
+    /// let country_code = "CH".parse().unwrap();
+    /// assert_eq!(formatter.languages(&country_code), ["de", "fr", "it"]);
+    /// ```
+    pub fn languages(&self, country_code: &CountryCode) -> &[String] {
+        self.country_to_lang
+            .get(country_code)
+            .map(|l| l.as_slice())
+            .unwrap_or_default()
+    }
+
     /// make a human readable text from a [`Place`](struct.Place.html)
     /// Same as the [`format`](struct.Formatter.html#method.format) method,
     /// but with a [`Configuration`](address_formatter::formatter::Configuration) object
@@ -220,64 +355,313 @@ impl Formatter {
         into_addr: impl Into<Place>,
         conf: Configuration,
     ) -> Result<String, Error> {
-        let mut addr = into_addr.into();
+        let addr: Place = into_addr.into();
+        let mut text = String::new();
+        self.format_into(addr, &conf, &mut text)?;
+        Ok(text)
+    }
+
+    /// format a [`Place`](struct.Place.html) as a [`FormattedAddress`](struct.FormattedAddress.html),
+    /// to get the lines of the formatted text without having to split it
+    pub fn format_lines(&self, into_addr: impl Into<Place>) -> Result<FormattedAddress, Error> {
+        self.format_lines_with_config(into_addr, Configuration::default())
+    }
+
+    /// Same as the [`format_lines`](struct.Formatter.html#method.format_lines) method,
+    /// but with a [`Configuration`](struct.Configuration.html) object
+    pub fn format_lines_with_config(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<FormattedAddress, Error> {
+        let addr: Place = into_addr.into();
+        self.with_formatted_text(addr.into(), &conf, FormattedAddress::from_text)
+    }
+
+    /// write the formatted place at the end of `out`, without copying the place.
+    ///
+    /// Same as [`format_with_config`](struct.Formatter.html#method.format_with_config),
+    /// but the place can be borrowed (as a `&Place` or a [`BorrowedPlace`](struct.BorrowedPlace.html))
+    /// and the intermediate buffers are reused between the calls, for the hot paths formatting lots of places.
+    ///
+    /// Once the buffers are large enough, nothing is allocated,
+    /// except for the values changed by the rules (like an abbreviation).
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::Component::*;
+    ///
+    ///    let places: Vec<address_formatter::Place> = vec![
+    ///        hashmap!(City => "Toulouse", CountryCode => "FR", Road => "Place du Capitole").into(),
+    ///        hashmap!(City => "Berlin", CountryCode => "DE", Road => "Unter den Linden").into(),
+    ///    ];
+    ///    let mut text = String::new();
+    ///    for place in &places {
+    ///        text.clear();
+    ///        address_formatter::FORMATTER
+    ///            .format_into(place, &Default::default(), &mut text)
+    ///            .unwrap();
+    ///        assert!(text.starts_with(place[Road].as_ref().unwrap()));
+    ///    }
+    /// # }
+    /// ```
+    pub fn format_into<'p>(
+        &self,
+        place: impl Into<BorrowedPlace<'p>>,
+        conf: &Configuration,
+        out: &mut impl fmt::Write,
+    ) -> Result<(), Error> {
+        Ok(self.with_formatted_text(place.into(), conf, |text| out.write_str(text))??)
+    }
+
+    /// format the place in the thread's buffers, and give the formatted text to `f`
+    fn with_formatted_text<R>(
+        &self,
+        place: BorrowedPlace,
+        conf: &Configuration,
+        f: impl FnOnce(&str) -> R,
+    ) -> Result<R, Error> {
+        thread_local! {
+            /// the buffers used to render and cleanup the text, reused between the calls
+            static BUFFERS: RefCell<(String, String)> = const { RefCell::new((String::new(), String::new())) };
+        }
+        let (addr, template, rules) = self.prepare(place, conf)?;
+
+        BUFFERS.with(|buffers| {
+            // the buffers are already used if `f` formats a place (like an `out` formatting a place while being written)
+            let mut fresh_buffers = Default::default();
+            let mut borrowed = buffers.try_borrow_mut();
+            let (text, scratch) = match &mut borrowed {
+                Ok(buffers) => &mut **buffers,
+                Err(_) => &mut fresh_buffers,
+            };
+            text.clear();
+            template.compiled.render(&addr, text);
+            cleanup_rendered(text, scratch, rules);
+            Ok(f(text))
+        })
+    }
+
+    /// make a one line human readable text from a [`Place`](struct.Place.html), for example for list views
+    ///
+    /// The components are in the order of the country's template (after the country's rules):
+    /// the ones on the same line of the template keep their separator, the lines are separated with ', '
+    /// and the duplicated components are removed.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::Component::*;
+    ///
+    ///    assert_eq!(
+    ///        address_formatter::FORMATTER
+    ///            .format_single_line(
+    ///                hashmap!(
+    ///                    City => "Toulouse",
+    ///                    Country => "France",
+    ///                    CountryCode => "FR",
+    ///                    HouseNumber => "17",
+    ///                    Postcode => "31000",
+    ///                    Road => "Rue du Médecin-Colonel Calbairac",
+    ///                ),
+    ///                Default::default()
+    ///            )
+    ///            .unwrap(),
+    ///        "17 Rue du Médecin-Colonel Calbairac, 31000 Toulouse, France"
+    ///    );
+    /// # }
+    /// ```
+    pub fn format_single_line(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<String, Error> {
+        Ok(self
+            .format_annotated_with_config(into_addr, conf)?
+            .single_line())
+    }
+
+    /// make a short one line label from a [`Place`](struct.Place.html), for example for autocompletion.
+    ///
+    /// Only the road and the administrative components down to `depth` are kept
+    /// (no house number, postcode, ...).
+    /// A place without city, town or village uses its suburb, neighbourhood or city district as locality.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::{AdminDepth, Component::*};
+    ///
+    ///    assert_eq!(
+    ///        address_formatter::FORMATTER
+    ///            .format_short(
+    ///                hashmap!(
+    ///                    City => "Toulouse",
+    ///                    Country => "France",
+    ///                    CountryCode => "FR",
+    ///                    HouseNumber => "17",
+    ///                    Postcode => "31000",
+    ///                    Road => "Rue du Médecin-Colonel Calbairac",
+    ///                ),
+    ///                Default::default(),
+    ///                AdminDepth::Locality,
+    ///            )
+    ///            .unwrap(),
+    ///        "Rue du Médecin-Colonel Calbairac, Toulouse"
+    ///    );
+    /// # }
+    /// ```
+    pub fn format_short(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+        depth: AdminDepth,
+    ) -> Result<String, Error> {
+        let addr = into_addr.into();
+        let mut short_addr = Place::default();
+        for c in depth.components() {
+            short_addr[*c] = addr[*c].clone();
+        }
+        let localities = [Component::City, Component::Town, Component::Village];
+        if depth != AdminDepth::Street && localities.iter().all(|c| short_addr[*c].is_none()) {
+            // the templates have no place for the parts of a city, so they are written as a village
+            short_addr[Component::Village] = [
+                Component::Suburb,
+                Component::Neighbourhood,
+                Component::CityDistrict,
+            ]
+            .iter()
+            .find_map(|c| addr[*c].clone());
+        }
+        // the country code is needed to find the template
+        short_addr[Component::CountryCode] = addr[Component::CountryCode].clone();
+        self.format_single_line(short_addr, conf)
+    }
+
+    /// the house number and the road, in the order of the country's template.
+    ///
+    /// Used for the formats with only one field for the street (like vCard or schema.org)
+    pub(crate) fn street(&self, addr: &Place, conf: &Configuration) -> Result<String, Error> {
+        Ok(self
+            .format_annotated_with_config(addr.clone(), conf.clone())?
+            .street())
+    }
+
+    /// format a [`Place`](struct.Place.html) as an [`AnnotatedAddress`](struct.AnnotatedAddress.html),
+    /// to know which part of the formatted text comes from which [`Component`](enum.Component.html)
+    pub fn format_annotated(&self, into_addr: impl Into<Place>) -> Result<AnnotatedAddress, Error> {
+        self.format_annotated_with_config(into_addr, Configuration::default())
+    }
+
+    /// Same as the [`format_annotated`](struct.Formatter.html#method.format_annotated) method,
+    /// but with a [`Configuration`](struct.Configuration.html) object
+    pub fn format_annotated_with_config(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<AnnotatedAddress, Error> {
+        let (addr, template, rules) = self.prepare(into_addr.into().into(), &conf)?;
+
+        // the values are wrapped with some markers, to be able to track them in the rendered text
+        let mut text = String::new();
+        template.compiled.render(&mark_place(&addr), &mut text);
+
+        let mut text = AnnotatedText::from_marked(&text);
+        cleanup(&mut text, rules);
+
+        Ok(text.into_address(&addr))
+    }
+
+    /// cleanup and preformat the place, and find the template and rules to use
+    ///
+    /// The place can borrow the values added by the rules, so it is bound to the formatter's lifetime
+    fn prepare<'a, 'p: 'a>(
+        &'a self,
+        addr: BorrowedPlace<'p>,
+        conf: &Configuration,
+    ) -> Result<(BorrowedPlace<'a>, &'a Template, &'a Rules), Error> {
+        let mut addr = addr.shorten();
         let country_code = self.find_country_code(&mut addr, conf);
 
         sanity_clean_place(&mut addr);
 
         let template = self.find_template(&addr, &country_code);
-        let rules = country_code
+        let rules = self.find_rules(&country_code);
+
+        self.preformat_with_config(&country_code, rules, conf, &mut addr)?;
+
+        Ok((addr, template, rules))
+    }
+
+    pub(crate) fn find_rules(&self, country_code: &Option<CountryCode>) -> &Rules {
+        country_code
             .as_ref()
             .and_then(|c| self.templates.rules_by_country.get(c))
-            .unwrap_or_else(|| &self.templates.fallback_rules);
-
-        self.preformat(&rules, &mut addr);
+            .unwrap_or(&self.templates.fallback_rules)
+    }
 
-        let text = template
-            .handlebar_handler
-            .render(TEMPLATE_NAME, &addr)
-            .map_err(|e| e.context("impossible to render template"))?;
+    /// apply the country's rules, and the configuration's ones (abbreviation and origin country)
+    pub(crate) fn preformat_with_config<'a>(
+        &'a self,
+        country_code: &Option<CountryCode>,
+        rules: &'a Rules,
+        conf: &Configuration,
+        addr: &mut BorrowedPlace<'a>,
+    ) -> Result<(), Error> {
+        self.preformat(rules, addr);
 
-        let text = cleanup_rendered(&text, &rules);
+        if conf.abbreviate.unwrap_or(false) {
+            let langs = self.find_languages(country_code, conf);
+            self.abbreviate(langs, addr);
+        }
 
-        Ok(text)
+        if let Some(origin_country) = &conf.origin_country {
+            apply_origin_country(origin_country, country_code, addr)?;
+        }
+        Ok(())
     }
 
-    fn find_country_code(&self, addr: &mut Place, conf: Configuration) -> Option<CountryCode> {
+    pub(crate) fn find_country_code(
+        &self,
+        addr: &mut BorrowedPlace,
+        conf: &Configuration,
+    ) -> Option<CountryCode> {
         let mut country_code = conf
             .country_code
-            .or_else(|| addr[Component::CountryCode].clone())
+            .as_deref()
+            .or_else(|| addr[Component::CountryCode].as_deref())
             .and_then(|s| {
-                CountryCode::from_str(&s)
+                CountryCode::from_str(s)
                     .map_err(|e| log::info!("impossible to find a country: {}", e))
                     .ok()
             });
 
         // we hardcode some country code values
-        if country_code == CountryCode::from_str("NL").ok() {
-            if let Some(state) = addr[Component::State].clone() {
-                if state.as_str() == "Curaçao" {
-                    country_code = CountryCode::from_str("CW").ok();
-                    addr[Component::Country] = Some("Curaçao".to_owned());
-                }
-                let state = state.to_lowercase();
-
-                if state.as_str() == "sint maarten" {
-                    country_code = CountryCode::from_str("SX").ok();
-                    addr[Component::Country] = Some("Sint Maarten".to_owned());
-                } else if state.as_str() == "aruba" {
-                    country_code = CountryCode::from_str("AW").ok();
-                    addr[Component::Country] = Some("Aruba".to_owned());
+        if country_code.as_ref().map(CountryCode::as_str) == Some("NL") {
+            let territory = addr[Component::State].as_deref().and_then(|state| {
+                if state == "Curaçao" {
+                    Some(("CW", "Curaçao"))
+                } else if state.eq_ignore_ascii_case("sint maarten") {
+                    Some(("SX", "Sint Maarten"))
+                } else if state.eq_ignore_ascii_case("aruba") {
+                    Some(("AW", "Aruba"))
+                } else {
+                    None
                 }
+            });
+            if let Some((territory_code, territory)) = territory {
+                country_code = CountryCode::from_str(territory_code).ok();
+                addr[Component::Country] = Some(territory.into());
             }
         }
 
         country_code
     }
 
-    fn find_template<'a>(
+    pub(crate) fn find_template<'a>(
         &'a self,
-        addr: &Place,
+        addr: &BorrowedPlace,
         country_code: &Option<CountryCode>,
     ) -> &'a Template {
         country_code
@@ -288,29 +672,29 @@ impl Formatter {
                     // if there is a specific one, else we get the default fallback template
                     self.templates
                         .fallback_templates_by_country
-                        .get(&c)
-                        .or_else(|| Some(&self.templates.fallback_template))
+                        .get(c)
+                        .or(Some(&self.templates.fallback_template))
                 } else {
-                    self.templates.templates_by_country.get(&c)
+                    self.templates.templates_by_country.get(c)
                 }
             })
             .unwrap_or(&self.templates.default_template)
     }
 
-    fn preformat(&self, rules: &Rules, addr: &mut Place) {
+    fn preformat<'a>(&'a self, rules: &'a Rules, addr: &mut BorrowedPlace<'a>) {
         for r in &rules.replace {
             r.replace_fields(addr);
         }
 
         // in some cases, we need to add some components
         if let Some(add_component) = &rules.add_component {
-            addr[add_component.component] = Some(add_component.new_value.clone());
+            addr[add_component.component] = Some(add_component.new_value.as_str().into());
         }
         if let Some(change_country) = &rules.change_country {
-            addr[Component::Country] = Some(change_country.clone());
+            addr[Component::Country] = Some(change_country.as_str().into());
         }
         if let Some(change_country_code) = &rules.change_country_code {
-            addr[Component::CountryCode] = Some(change_country_code.clone());
+            addr[Component::CountryCode] = Some(change_country_code.as_str().into());
         }
 
         // we also try to find the state_code/county_code
@@ -320,27 +704,46 @@ impl Formatter {
         {
             if addr[Component::StateCode].is_none() {
                 // we try to see if we can use the state_code and the reference table 'state_codes.yaml' to find the state
-                if let Some(state) = &addr[Component::State] {
-                    if let Some(new_state) = self
-                        .state_codes
-                        .get(&(country.clone(), state.to_string()))
-                        .cloned()
-                    {
-                        addr[Component::StateCode] = Some(new_state);
-                    }
+                if let Some(new_state) = addr[Component::State]
+                    .as_deref()
+                    .and_then(|state| self.state_codes.get(&country)?.get(state))
+                {
+                    addr[Component::StateCode] = Some(new_state.as_str().into());
                 }
             }
 
             if addr[Component::CountyCode].is_none() {
                 // same for county
-                if let Some(county) = &addr[Component::County] {
-                    if let Some(new_county) = self
-                        .county_codes
-                        .get(&(country, county.to_string()))
-                        .cloned()
-                    {
-                        addr[Component::County] = Some(new_county);
-                    }
+                if let Some(new_county) = addr[Component::County]
+                    .as_deref()
+                    .and_then(|county| self.county_codes.get(&country)?.get(county))
+                {
+                    addr[Component::County] = Some(new_county.as_str().into());
+                }
+            }
+        }
+    }
+
+    fn find_languages<'a>(
+        &'a self,
+        country_code: &Option<CountryCode>,
+        conf: &'a Configuration,
+    ) -> &'a [String] {
+        if let Some(lang) = &conf.language {
+            std::slice::from_ref(lang)
+        } else {
+            country_code
+                .as_ref()
+                .map(|c| self.languages(c))
+                .unwrap_or_default()
+        }
+    }
+
+    fn abbreviate(&self, langs: &[String], addr: &mut BorrowedPlace) {
+        for lang in langs {
+            if let Some(rules) = self.abbreviations.get(lang.as_str()) {
+                for r in rules {
+                    r.replace_fields(addr);
                 }
             }
         }
@@ -350,17 +753,43 @@ impl Formatter {
 /// Build [`Place`](struct.Place.html) from a less structured input (like placees from [Nominatim](https://github.com/openstreetmap/Nominatim))
 ///
 /// It applies aliases rules to fill the [`Place`](struct.Place.html)'s fields as good as possible.
+///
+/// For the Nominatim results, [`build_place_from_nominatim`](struct.PlaceBuilder.html#method.build_place_from_nominatim)
+/// also handles the structure of the result.
 pub struct PlaceBuilder {
     pub(crate) component_aliases: HashMap<Component, Vec<String>>,
 }
 
 impl Default for PlaceBuilder {
     fn default() -> Self {
-        crate::read_configuration::read_place_builder_configuration()
+        Self::try_new().unwrap_or_else(|e| panic!("{}", e))
     }
 }
 
 impl PlaceBuilder {
+    /// Build a [`PlaceBuilder`](struct.PlaceBuilder.html) with the opencage configuration embedded in the binary.
+    ///
+    /// Same as [`default`](struct.PlaceBuilder.html#method.default), but returns an error instead of panicking
+    /// if the configuration is invalid
+    pub fn try_new() -> Result<Self, ConfigurationError> {
+        crate::read_configuration::read_place_builder_configuration(
+            crate::read_configuration::embedded_components_file(),
+        )
+    }
+
+    /// Build a [`PlaceBuilder`](struct.PlaceBuilder.html) with the opencage configuration read at runtime
+    /// from a directory (the `conf` directory of the [opencage repository](https://github.com/OpenCageData/address-formatting)).
+    pub fn from_config_dir(config_dir: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::try_from_config_dir(config_dir)?)
+    }
+
+    /// Same as [`from_config_dir`](struct.PlaceBuilder.html#method.from_config_dir), but with a
+    /// [`ConfigurationError`](struct.ConfigurationError.html) listing all the problems found in the configuration
+    pub fn try_from_config_dir(config_dir: impl AsRef<Path>) -> Result<Self, ConfigurationError> {
+        let components_file = crate::read_configuration::read_components_file(config_dir.as_ref())?;
+        crate::read_configuration::read_place_builder_configuration(&components_file)
+    }
+
     /// Build a [`Place`](struct.Place.html)(crate::Place) from an unstructed source (like Nominatim output)
     pub fn build_place<'a>(&self, values: impl IntoIterator<Item = (&'a str, String)>) -> Place {
         let mut place = Place::default();
@@ -386,7 +815,9 @@ impl PlaceBuilder {
                     }
                 }
             }
-            place[Component::Attention] = Some(unknown.values().join(", "));
+            if !unknown.is_empty() {
+                place[Component::Attention] = Some(unknown.values().join(", "));
+            }
         }
 
         // hardocded cleanup for some bad country data
@@ -399,9 +830,37 @@ impl PlaceBuilder {
         }
         place
     }
+
+    /// true if the key is a component name or one of their aliases
+    pub(crate) fn is_known(&self, key: &str) -> bool {
+        Component::from_str(key).is_ok()
+            || self
+                .component_aliases
+                .values()
+                .any(|aliases| aliases.iter().any(|a| a == key))
+    }
+}
+
+/// the text of a JSON value given to [`PlaceBuilder::build_place`](struct.PlaceBuilder.html#method.build_place)
+/// (like the values of a JSON object keyed by component names).
+///
+/// The strings are kept as is, the other values (like the numbers) are written as JSON, and `null` gives `None`.
+///
+/// ```
+/// use address_formatter::json_value_to_string;
+/// assert_eq!(json_value_to_string(serde_json::json!("Toulouse")).as_deref(), Some("Toulouse"));
+/// assert_eq!(json_value_to_string(serde_json::json!(17)).as_deref(), Some("17"));
+/// assert_eq!(json_value_to_string(serde_json::Value::Null), None);
+/// ```
+pub fn json_value_to_string(value: serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s),
+        v => Some(v.to_string()),
+    }
 }
 
-fn sanity_clean_place(addr: &mut Place) {
+pub(crate) fn sanity_clean_place(addr: &mut BorrowedPlace) {
     lazy_static::lazy_static! {
         static ref POST_CODE_RANGE: Regex = Regex::new(r#"\d+;\d+"#).unwrap();
         static ref MATCHABLE_POST_CODE_RANGE: Regex = Regex::new(r#"^(\d{5}),\d{5}"#).unwrap();
@@ -412,12 +871,13 @@ fn sanity_clean_place(addr: &mut Place) {
     if let Some(post_code) = &addr[Component::Postcode] {
         if post_code.len() > 20 || POST_CODE_RANGE.is_match(post_code) {
             addr[Component::Postcode] = None;
-        } else if let Some(r) = MATCHABLE_POST_CODE_RANGE
-            .captures(post_code)
-            .and_then(|r| r.get(1))
-            .map(|c| c.as_str())
-        {
-            addr[Component::Postcode] = Some(r.to_owned());
+        } else if MATCHABLE_POST_CODE_RANGE.is_match(post_code) {
+            // only the first postcode of the range is kept
+            // (the captures are allocated, so they are only read for a range)
+            addr[Component::Postcode] = MATCHABLE_POST_CODE_RANGE
+                .captures(post_code)
+                .and_then(|r| r.get(1))
+                .map(|c| c.as_str().to_owned().into());
         }
     }
 
@@ -431,107 +891,191 @@ fn sanity_clean_place(addr: &mut Place) {
     }
 }
 
-fn cleanup_rendered(text: &str, rules: &Rules) -> String {
-    use itertools::Itertools;
-    lazy_static::lazy_static! {
-        static ref REPLACEMENTS:  [(Regex, &'static str); 12]= [
-            (RegexBuilder::new(r"[},\s]+$").multi_line(true).build().unwrap(), ""),
-            (RegexBuilder::new(r"^ - ").multi_line(true).build().unwrap(), ""), // line starting with dash due to a parameter missing
-            (RegexBuilder::new(r"^[,\s]+").multi_line(true).build().unwrap(), ""),
-            (RegexBuilder::new(r",\s*,").multi_line(true).build().unwrap(), ", "), //multiple commas to one
-            (RegexBuilder::new(r"[\t\p{Zs}]+,[\t\p{Zs}]+").multi_line(true).build().unwrap(), ", "), //one horiz whitespace behind comma
-            (RegexBuilder::new(r"[\t ][\t ]+").multi_line(true).build().unwrap(), " "), //multiple horiz whitespace to one
-            (RegexBuilder::new(r"[\t\p{Zs}]\n").multi_line(true).build().unwrap(), "\n"), //horiz whitespace, newline to newline
-            (RegexBuilder::new(r"\n,").multi_line(true).build().unwrap(), "\n"), //newline comma to just newline
-            (RegexBuilder::new(r",,+").multi_line(true).build().unwrap(), ","), //multiple commas to one
-            (RegexBuilder::new(r",\n").multi_line(true).build().unwrap(), "\n"), //comma newline to just newline
-            (RegexBuilder::new(r"\n[\t\p{Zs}]+").multi_line(true).build().unwrap(), "\n"), //newline plus space to newline
-            (RegexBuilder::new(r"\n\n+").multi_line(true).build().unwrap(), "\n"), //multiple newline to one
-        ];
+lazy_static::lazy_static! {
+    static ref REPLACEMENTS: [(Regex, &'static str); 12] = [
+        (RegexBuilder::new(r"[},\s]+$").multi_line(true).build().unwrap(), ""),
+        (RegexBuilder::new(r"^ - ").multi_line(true).build().unwrap(), ""), // line starting with dash due to a parameter missing
+        (RegexBuilder::new(r"^[,\s]+").multi_line(true).build().unwrap(), ""),
+        (RegexBuilder::new(r",\s*,").multi_line(true).build().unwrap(), ", "), // multiple commas to one
+        (RegexBuilder::new(r"[\t\p{Zs}]+,[\t\p{Zs}]+").multi_line(true).build().unwrap(), ", "), // one horiz whitespace behind comma
+        (RegexBuilder::new(r"[\t ][\t ]+").multi_line(true).build().unwrap(), " "), // multiple horiz whitespace to one
+        (RegexBuilder::new(r"[\t\p{Zs}]\n").multi_line(true).build().unwrap(), "\n"), // horiz whitespace, newline to newline
+        (RegexBuilder::new(r"\n,").multi_line(true).build().unwrap(), "\n"), // newline comma to just newline
+        (RegexBuilder::new(r",,+").multi_line(true).build().unwrap(), ","), // multiple commas to one
+        (RegexBuilder::new(r",\n").multi_line(true).build().unwrap(), "\n"), // comma newline to just newline
+        (RegexBuilder::new(r"\n[\t\p{Zs}]+").multi_line(true).build().unwrap(), "\n"), // newline plus space to newline
+        (RegexBuilder::new(r"\n\n+").multi_line(true).build().unwrap(), "\n"), // multiple newline to one
+    ];
+}
 
-        static ref FINAL_CLEANUP:  [(Regex, &'static str); 2]= [
-            (Regex::new(r"^\s+").unwrap(), ""), //remove leading whitespace
-            (Regex::new(r"\s+$").unwrap(), ""), //remove end whitespace
-        ];
+/// A rendered text, cleaned up with [`cleanup`](fn.cleanup.html).
+///
+/// Both the plain text and the annotated text (keeping track of the components) go through the same steps,
+/// so the annotated text is always the same as the plain one
+pub(crate) trait RenderedText {
+    /// replace all the matches of a regex
+    fn replace_all(&mut self, regex: &Regex, replacement: &str);
+    /// dedup and trim all the same 'token' (separated by ', ') of a line, and all the same lines too
+    fn dedup(&mut self);
+    /// remove the leading and trailing whitespaces
+    fn trim(&mut self);
+}
+
+/// the steps of the cleanup of a rendered text
+pub(crate) fn cleanup(text: &mut impl RenderedText, rules: &Rules) {
+    for (rgx, new_val) in REPLACEMENTS.iter() {
+        text.replace_all(rgx, new_val);
     }
 
-    let mut res = text.to_owned();
+    for r in &rules.postformat_replace {
+        text.replace_all(&r.regex, r.replacement_value.as_str());
+    }
 
-    for (rgx, new_val) in REPLACEMENTS.iter() {
-        let rep = rgx.replace_all(&res, *new_val);
-        // to improve performance, we update the string only if it was changed by the replace
-        match rep {
-            std::borrow::Cow::Borrowed(_) => {}
-            std::borrow::Cow::Owned(v) => {
-                res = v;
+    text.dedup();
+    text.trim();
+}
+
+/// a plain rendered text, `scratch` being used as a buffer to change the text in place
+struct PlainText<'b> {
+    text: &'b mut String,
+    scratch: &'b mut String,
+}
+
+impl RenderedText for PlainText<'_> {
+    fn replace_all(&mut self, regex: &Regex, replacement: &str) {
+        replace_all(regex, replacement, self.text, self.scratch);
+    }
+
+    fn dedup(&mut self) {
+        let (text, scratch) = (&mut *self.text, &mut *self.scratch);
+        scratch.clear();
+        let mut last_line_start = None;
+        for line in text.split('\n') {
+            let end_of_last_line = scratch.len();
+            if last_line_start.is_some() {
+                scratch.push('\n');
+            }
+            let line_start = scratch.len();
+            push_dedup_tokens(line, scratch);
+            if let Some(last_line_start) = last_line_start {
+                if scratch[last_line_start..end_of_last_line] == scratch[line_start..] {
+                    scratch.truncate(end_of_last_line);
+                    continue;
+                }
             }
+            last_line_start = Some(line_start);
         }
+        std::mem::swap(text, scratch);
     }
 
-    for r in &rules.postformat_replace {
-        let rep = r.regex.replace_all(&res, r.replacement_value.as_str());
-        match rep {
-            std::borrow::Cow::Borrowed(_) => {}
-            std::borrow::Cow::Owned(v) => {
-                res = v;
-            }
+    fn trim(&mut self) {
+        self.text.truncate(self.text.trim_end().len());
+        let leading = self.text.len() - self.text.trim_start().len();
+        self.text.drain(..leading);
+    }
+}
+
+/// cleanup the rendered text in place and add a final newline, `scratch` being used as a buffer for the replacements
+pub(crate) fn cleanup_rendered(text: &mut String, scratch: &mut String, rules: &Rules) {
+    cleanup(&mut PlainText { text, scratch }, rules);
+    text.push('\n');
+}
+
+/// same as `Regex::replace_all`, but the text is replaced in place, using `scratch` as a buffer
+fn replace_all(regex: &Regex, replacement: &str, text: &mut String, scratch: &mut String) {
+    scratch.clear();
+    let mut last = 0;
+    let mut replaced = false;
+    if replacement.contains('$') {
+        for caps in regex.captures_iter(text) {
+            let m = caps.get(0).expect("a capture always has a match");
+            scratch.push_str(&text[last..m.start()]);
+            caps.expand(replacement, scratch);
+            last = m.end();
+            replaced = true;
         }
+    } else {
+        for m in regex.find_iter(text) {
+            scratch.push_str(&text[last..m.start()]);
+            scratch.push_str(replacement);
+            last = m.end();
+            replaced = true;
+        }
+    }
+    if replaced {
+        scratch.push_str(&text[last..]);
+        std::mem::swap(text, scratch);
     }
+}
 
-    // we also dedup the string
-    // we dedup and trim and all the same 'token' in a line
-    // and all the same lines too
-    let mut res = res
-        .split('\n')
-        .map(|s| s.split(", ").map(|e| e.trim()).dedup().join(", "))
-        .dedup()
-        .join("\n");
-
-    for (rgx, new_val) in FINAL_CLEANUP.iter() {
-        let rep = rgx.replace(&res, *new_val);
-        match rep {
-            std::borrow::Cow::Borrowed(_) => {}
-            std::borrow::Cow::Owned(v) => {
-                res = v;
-            }
+/// dedup and trim all the same 'token' (separated by ', ') of a line, written at the end of `out`
+fn push_dedup_tokens(line: &str, out: &mut String) {
+    let mut last = None;
+    for token in line.split(", ").map(|t| t.trim()) {
+        if last == Some(token) {
+            continue;
+        }
+        if last.is_some() {
+            out.push_str(", ");
         }
+        out.push_str(token);
+        last = Some(token);
     }
+}
+
+fn apply_origin_country(
+    origin_country: &str,
+    country_code: &Option<CountryCode>,
+    addr: &mut BorrowedPlace,
+) -> Result<(), Error> {
+    let origin_country = CountryCode::from_str(origin_country)
+        .map_err(|e| format_err!("invalid origin_country: {}", e))?;
+    // the country code can have been changed by the preformating
+    // (for territories using the rules of another country)
+    let destination_country = addr[Component::CountryCode]
+        .as_ref()
+        .and_then(|c| CountryCode::from_str(c).ok())
+        .or_else(|| country_code.clone());
 
-    let res = res.trim();
-    format!("{}\n", res) //add final newline
+    if destination_country.as_ref() == Some(&origin_country) {
+        // domestic mail, no need for the country
+        addr[Component::Country] = None;
+    } else if let Some(country) = &addr[Component::Country] {
+        // international mail, the Universal Postal Union recommends capital letters for the country
+        addr[Component::Country] = Some(country.to_uppercase().into());
+    }
+    Ok(())
 }
 
-fn has_minimum_place_components(addr: &Place) -> bool {
+fn has_minimum_place_components(addr: &BorrowedPlace) -> bool {
     // if there are neither 'road' nor 'postcode', we consider that there are not enough data
     // and use the fallback template
     addr[Component::Road].is_some() || addr[Component::Postcode].is_some()
 }
 
 impl ReplaceRule {
-    fn replace_fields(&self, addr: &mut Place) {
+    fn replace_fields(&self, addr: &mut BorrowedPlace) {
         match self {
             ReplaceRule::All(replace_rule) => {
                 for c in Component::iter() {
-                    if let Some(v) = &addr[c] {
-                        addr[c] = Some(
-                            replace_rule
-                                .regex
-                                .replace(&v, replace_rule.replacement_value.as_str())
-                                .to_string(),
-                        );
-                    }
-                }
-            }
-            ReplaceRule::Component((c, replace_rule)) => {
-                if let Some(v) = &addr[*c] {
-                    addr[*c] = Some(
-                        replace_rule
-                            .regex
-                            .replace(&v, replace_rule.replacement_value.as_str())
-                            .to_string(),
-                    );
+                    replace_rule.replace_field(addr, c);
                 }
             }
+            ReplaceRule::Component((c, replace_rule)) => replace_rule.replace_field(addr, *c),
         }
     }
 }
+
+impl Replacement {
+    /// replace the first match of a component's value, the value being copied only if it is changed
+    fn replace_field(&self, addr: &mut BorrowedPlace, component: Component) {
+        let replaced = match addr[component]
+            .as_ref()
+            .map(|v| self.regex.replace(v, self.replacement_value.as_str()))
+        {
+            Some(Cow::Owned(replaced)) => replaced,
+            _ => return,
+        };
+        addr[component] = Some(replaced.into());
+    }
+}
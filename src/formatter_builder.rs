@@ -0,0 +1,240 @@
+use crate::error::{ConfigurationError, ConfigurationIssue};
+use crate::formatter::{CountryCode, Formatter, ReplaceRule, Template};
+use crate::read_configuration::{build_replace_rule, use_country};
+use std::str::FromStr;
+
+const BUILDER: &str = "FormatterBuilder";
+
+/// Build a [`Formatter`](struct.Formatter.html) with some custom rules on top of an existing configuration
+/// (by default the opencage configuration embedded in the binary).
+///
+/// All the problems (invalid country code, template or regex) are reported when calling `build`.
+///
+/// ```
+/// # #[macro_use] extern crate maplit;
+/// # fn main() {
+///    use address_formatter::Component::*;
+///
+///    let formatter = address_formatter::FormatterBuilder::default()
+///        .address_template(
+///            "FR",
+///            r#"{{{road}}} {{{house_number}}}
+///{{{postcode}}} {{{city}}}"#,
+///        )
+///        .replace("FR", "road=^Rue ", "R. ")
+///        .build()
+///        .unwrap();
+///
+///    assert_eq!(
+///        formatter
+///            .format(hashmap!(
+///                City => "Toulouse",
+///                CountryCode => "FR",
+///                HouseNumber => "17",
+///                Postcode => "31000",
+///                Road => "Rue du Médecin-Colonel Calbairac",
+///            ))
+///            .unwrap(),
+///        r#"R. du Médecin-Colonel Calbairac 17
+///31000 Toulouse
+///"#
+///        .to_owned()
+///    )
+/// # }
+/// ```
+pub struct FormatterBuilder {
+    formatter: Formatter,
+    issues: Vec<ConfigurationIssue>,
+}
+
+impl Default for FormatterBuilder {
+    /// start from the opencage configuration embedded in the binary
+    fn default() -> Self {
+        Formatter::default().into()
+    }
+}
+
+impl From<Formatter> for FormatterBuilder {
+    /// start from the configuration of an existing [`Formatter`](struct.Formatter.html)
+    fn from(formatter: Formatter) -> Self {
+        FormatterBuilder {
+            formatter,
+            issues: vec![],
+        }
+    }
+}
+
+impl FormatterBuilder {
+    /// replace (or add) the address template of a country
+    ///
+    /// the countries already using the template of this country (through `use_country`) are not changed
+    pub fn address_template(mut self, country_code: &str, template: &str) -> Self {
+        let rule = "address_template";
+        if let (Some(country_code), Some(template)) = (
+            self.country_code(country_code, rule),
+            self.template(country_code, rule, template),
+        ) {
+            self.formatter
+                .templates
+                .templates_by_country
+                .insert(country_code, template);
+        }
+        self
+    }
+
+    /// replace (or add) the fallback template of a country
+    ///
+    /// the fallback template is used when the place has neither a road nor a postcode
+    pub fn fallback_template(mut self, country_code: &str, template: &str) -> Self {
+        let rule = "fallback_template";
+        if let (Some(country_code), Some(template)) = (
+            self.country_code(country_code, rule),
+            self.template(country_code, rule, template),
+        ) {
+            self.formatter
+                .templates
+                .fallback_templates_by_country
+                .insert(country_code, template);
+        }
+        self
+    }
+
+    /// add a replace rule for a country, applied on the place's components before formatting
+    ///
+    /// like in the opencage configuration, the pattern is either a regex, applied on all the components,
+    /// or written 'component=<regex>' to be applied on only one component.
+    pub fn replace(mut self, country_code: &str, pattern: &str, replacement: &str) -> Self {
+        let rule = format!("replace: {}", pattern);
+        if let (Some(country_code), Some(replace_rule)) = (
+            self.country_code(country_code, &rule),
+            self.replace_rule(country_code, &rule, pattern, replacement),
+        ) {
+            self.formatter
+                .templates
+                .rules_by_country
+                .entry(country_code)
+                .or_default()
+                .replace
+                .push(replace_rule);
+        }
+        self
+    }
+
+    /// add a replace rule for a country, applied on the formatted text
+    pub fn postformat_replace(
+        mut self,
+        country_code: &str,
+        pattern: &str,
+        replacement: &str,
+    ) -> Self {
+        let rule = format!("postformat_replace: {}", pattern);
+        let replace_rule = self.replace_rule(country_code, &rule, pattern, replacement);
+        let replacement = match replace_rule {
+            Some(ReplaceRule::All(r)) => Some(r),
+            Some(ReplaceRule::Component(_)) => {
+                self.issue(
+                    country_code,
+                    &rule,
+                    "postformat rules cannot be applied on only one element",
+                );
+                None
+            }
+            None => None,
+        };
+        if let (Some(country_code), Some(replacement)) =
+            (self.country_code(country_code, &rule), replacement)
+        {
+            self.formatter
+                .templates
+                .rules_by_country
+                .entry(country_code)
+                .or_default()
+                .postformat_replace
+                .push(replacement);
+        }
+        self
+    }
+
+    /// make a country use the template and the rules of another country
+    /// (like the `use_country` of the opencage configuration)
+    ///
+    /// the configuration of the parent country at the time of the call is used,
+    /// so the parent country should be customized first.
+    pub fn use_country(mut self, country_code: &str, parent_country_code: &str) -> Self {
+        let rule = "use_country";
+        if let (Some(country), Some(parent)) = (
+            self.country_code(country_code, rule),
+            self.country_code(parent_country_code, rule),
+        ) {
+            let templates = &mut self.formatter.templates;
+            if let Err(e) = use_country(
+                &mut templates.templates_by_country,
+                &mut templates.rules_by_country,
+                &country,
+                &parent,
+            ) {
+                self.issue(country_code, rule, e);
+            }
+        }
+        self
+    }
+
+    /// change the country name of the places of a country
+    /// (like the `change_country` of the opencage configuration)
+    pub fn change_country(mut self, country_code: &str, country: &str) -> Self {
+        if let Some(country_code) = self.country_code(country_code, "change_country") {
+            self.formatter
+                .templates
+                .rules_by_country
+                .entry(country_code)
+                .or_default()
+                .change_country = Some(country.to_owned());
+        }
+        self
+    }
+
+    /// build the [`Formatter`](struct.Formatter.html),
+    /// or return a [`ConfigurationError`](struct.ConfigurationError.html) with all the problems found
+    pub fn build(self) -> Result<Formatter, ConfigurationError> {
+        if self.issues.is_empty() {
+            Ok(self.formatter)
+        } else {
+            Err(ConfigurationError {
+                issues: self.issues,
+            })
+        }
+    }
+
+    fn issue(&mut self, country_code: &str, rule: &str, message: impl ToString) {
+        self.issues.push(ConfigurationIssue {
+            file: BUILDER.to_owned(),
+            country: Some(country_code.to_owned()),
+            rule: Some(rule.to_owned()),
+            message: message.to_string(),
+        });
+    }
+
+    fn country_code(&mut self, country_code: &str, rule: &str) -> Option<CountryCode> {
+        CountryCode::from_str(country_code)
+            .map_err(|e| self.issue(country_code, rule, e))
+            .ok()
+    }
+
+    fn template(&mut self, country_code: &str, rule: &str, template: &str) -> Option<Template> {
+        Template::new(template)
+            .map_err(|e| self.issue(country_code, rule, e))
+            .ok()
+    }
+
+    fn replace_rule(
+        &mut self,
+        country_code: &str,
+        rule: &str,
+        pattern: &str,
+        replacement: &str,
+    ) -> Option<ReplaceRule> {
+        build_replace_rule(pattern, replacement.to_owned())
+            .map_err(|e| self.issue(country_code, rule, e))
+            .ok()
+    }
+}
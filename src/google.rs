@@ -0,0 +1,168 @@
+use crate::{Component, Place};
+use serde::{Deserialize, Serialize};
+
+/// An element of the `address_components` of a
+/// [Google Geocoding API](https://developers.google.com/maps/documentation/geocoding/requests-geocoding#GeocodingResponses) result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GoogleAddressComponent {
+    /// the full name of the component (like "New York")
+    pub long_name: String,
+    /// the abbreviated name of the component, if any (like "NY")
+    pub short_name: String,
+    /// the types of the component (like `administrative_area_level_1` and `political`)
+    pub types: Vec<String>,
+}
+
+/// the Google types and the component they are read into, in priority order
+/// (the first one found is used for a component)
+const GOOGLE_TYPES: [(&str, Component); 15] = [
+    ("street_number", Component::HouseNumber),
+    ("premise", Component::House),
+    ("route", Component::Road),
+    ("neighborhood", Component::Neighbourhood),
+    ("sublocality_level_1", Component::Suburb),
+    ("sublocality", Component::Suburb),
+    ("locality", Component::City),
+    ("postal_town", Component::City),
+    ("administrative_area_level_3", Component::City),
+    ("administrative_area_level_2", Component::County),
+    ("administrative_area_level_1", Component::State),
+    ("postal_code", Component::Postcode),
+    ("country", Component::Country),
+    ("point_of_interest", Component::Attention),
+    ("establishment", Component::Attention),
+];
+
+/// the component giving the `short_name` of another one
+fn short_name_component(component: Component) -> Option<Component> {
+    match component {
+        Component::County => Some(Component::CountyCode),
+        Component::State => Some(Component::StateCode),
+        Component::Country => Some(Component::CountryCode),
+        _ => None,
+    }
+}
+
+impl Place {
+    /// Build a [`Place`](struct.Place.html) from the `address_components` of a Google Geocoding API result.
+    ///
+    /// The `short_name` of the state and the country are used as `StateCode` and `CountryCode`
+    /// (unless it is the same as the `long_name`, which is not a code).
+    ///
+    /// ```
+    /// use address_formatter::{GoogleAddressComponent, Place};
+    ///
+    /// let components: Vec<GoogleAddressComponent> = serde_json::from_str(r#"[
+    ///     {"long_name": "301", "short_name": "301", "types": ["street_number"]},
+    ///     {"long_name": "Hicks Street", "short_name": "Hicks St", "types": ["route"]},
+    ///     {"long_name": "Brooklyn", "short_name": "Brooklyn", "types": ["political", "sublocality", "sublocality_level_1"]},
+    ///     {"long_name": "New York", "short_name": "NY", "types": ["administrative_area_level_1", "political"]},
+    ///     {"long_name": "United States", "short_name": "US", "types": ["country", "political"]},
+    ///     {"long_name": "11201", "short_name": "11201", "types": ["postal_code"]}
+    /// ]"#).unwrap();
+    /// let place = Place::from_google_address_components(&components);
+    ///
+    /// assert_eq!(
+    ///     address_formatter::FORMATTER.format(place).unwrap(),
+    ///     "301 Hicks Street\nBrooklyn, NY 11201\nUnited States of America\n"
+    /// );
+    /// ```
+    pub fn from_google_address_components(components: &[GoogleAddressComponent]) -> Place {
+        let mut place = Place::default();
+        for (google_type, component) in GOOGLE_TYPES.iter() {
+            if place[*component].is_some() {
+                continue;
+            }
+            let found = components
+                .iter()
+                .find(|c| c.types.iter().any(|t| t == google_type));
+            if let Some(found) = found {
+                place[*component] = Some(found.long_name.clone());
+                if let Some(short_component) = short_name_component(*component) {
+                    if !found.short_name.is_empty() && found.short_name != found.long_name {
+                        place[short_component] = Some(found.short_name.clone());
+                    }
+                }
+            }
+        }
+        place
+    }
+
+    /// Convert the [`Place`](struct.Place.html) to the `address_components` of the Google Geocoding API,
+    /// from the smallest to the largest component (like in the API results).
+    ///
+    /// The codes (like `StateCode`) are used as `short_name`.
+    ///
+    /// ```
+    /// use address_formatter::{Component, Place};
+    ///
+    /// let mut place = Place::default();
+    /// place[Component::Road] = Some("Hicks Street".to_owned());
+    /// place[Component::State] = Some("New York".to_owned());
+    /// place[Component::StateCode] = Some("NY".to_owned());
+    ///
+    /// let components = place.to_google_address_components();
+    /// assert_eq!(components[1].long_name, "New York");
+    /// assert_eq!(components[1].short_name, "NY");
+    /// assert_eq!(components[1].types, ["administrative_area_level_1", "political"]);
+    /// ```
+    pub fn to_google_address_components(&self) -> Vec<GoogleAddressComponent> {
+        let first = |components: &[Component]| components.iter().find_map(|c| self[*c].clone());
+        let elements: [(Option<String>, Option<String>, &[&str]); 11] = [
+            (
+                self[Component::Attention].clone(),
+                None,
+                &["point_of_interest", "establishment"],
+            ),
+            (
+                self[Component::HouseNumber].clone(),
+                None,
+                &["street_number"],
+            ),
+            (self[Component::House].clone(), None, &["premise"]),
+            (self[Component::Road].clone(), None, &["route"]),
+            (
+                self[Component::Neighbourhood].clone(),
+                None,
+                &["neighborhood", "political"],
+            ),
+            (
+                first(&[Component::Suburb, Component::CityDistrict]),
+                None,
+                &["political", "sublocality", "sublocality_level_1"],
+            ),
+            (
+                first(&[Component::City, Component::Town, Component::Village]),
+                None,
+                &["locality", "political"],
+            ),
+            (
+                self[Component::County].clone(),
+                self[Component::CountyCode].clone(),
+                &["administrative_area_level_2", "political"],
+            ),
+            (
+                self[Component::State].clone(),
+                self[Component::StateCode].clone(),
+                &["administrative_area_level_1", "political"],
+            ),
+            (
+                first(&[Component::Country, Component::CountryCode]),
+                self[Component::CountryCode].clone(),
+                &["country", "political"],
+            ),
+            (self[Component::Postcode].clone(), None, &["postal_code"]),
+        ];
+        elements
+            .iter()
+            .filter_map(|(long_name, short_name, types)| {
+                let long_name = long_name.clone()?;
+                Some(GoogleAddressComponent {
+                    short_name: short_name.clone().unwrap_or_else(|| long_name.clone()),
+                    long_name,
+                    types: types.iter().map(|t| t.to_string()).collect(),
+                })
+            })
+            .collect()
+    }
+}
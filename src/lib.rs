@@ -64,7 +64,7 @@
 //!    ];
 //!
 //!    let addr =
-//!        addr_builder.build_place(data.into_iter().map(|(k, v)| (k.clone(), v.to_string())));
+//!        addr_builder.build_place(data.iter().map(|(k, v)| (*k, v.to_string())));
 //!
 //!    assert_eq!(
 //!        formatter.format(addr).unwrap(),
@@ -78,13 +78,40 @@
 //! # }
 //! ```
 
+pub(crate) mod address_lines;
+pub(crate) mod annotated_address;
+pub(crate) mod batch;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+pub(crate) mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub(crate) mod formatted_address;
 pub(crate) mod formatter;
-pub(crate) mod handlebar_helper;
+pub(crate) mod formatter_builder;
+pub(crate) mod google;
+pub(crate) mod markup;
+pub(crate) mod nominatim;
+pub(crate) mod osm;
 pub(crate) mod place;
 pub(crate) mod read_configuration;
+pub(crate) mod template_engine;
+pub(crate) mod vcard;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub use formatter::{Configuration, Formatter, PlaceBuilder};
-pub use place::{Component, Place};
+pub use address_lines::AddressLines;
+pub use annotated_address::AnnotatedAddress;
+pub use error::{ConfigurationError, ConfigurationIssue};
+pub use formatted_address::FormattedAddress;
+pub use formatter::{
+    json_value_to_string, AdminDepth, Configuration, CountryCode, Formatter, PlaceBuilder,
+};
+pub use formatter_builder::FormatterBuilder;
+pub use google::GoogleAddressComponent;
+pub use place::{deserialize_place_with_aliases, BorrowedPlace, Component, Place};
+pub use vcard::VCardAddress;
 
 lazy_static::lazy_static! {
     /// Singleton to ease use of the [`Formatter`](struct.Formatter.html)
@@ -0,0 +1,180 @@
+use crate::template_engine::escape_html;
+use crate::{Component, Configuration, Formatter, Place};
+use failure::Error;
+use serde_json::{Map, Value};
+
+/// the [microformats](http://microformats.org/wiki/h-adr) class of a component in a `h-adr`
+/// (the `Attention` has no class, a name being an `h-card` property)
+fn h_adr_class(component: Component) -> Option<&'static str> {
+    match component {
+        Component::House => Some("p-extended-address"),
+        Component::HouseNumber | Component::Road => Some("p-street-address"),
+        Component::City | Component::Town | Component::Village => Some("p-locality"),
+        Component::State | Component::StateCode => Some("p-region"),
+        Component::Postcode => Some("p-postal-code"),
+        Component::Country => Some("p-country-name"),
+        _ => None,
+    }
+}
+
+impl Formatter {
+    /// make a schema.org [`PostalAddress`](https://schema.org/PostalAddress) of a [`Place`](struct.Place.html),
+    /// to be embedded as JSON-LD.
+    ///
+    /// The house number and the road are written in the order of the country's template,
+    /// and the country is given by its ISO 3166-1 code when known.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::Component::*;
+    ///
+    ///    let json_ld = address_formatter::FORMATTER
+    ///        .postal_address_json_ld(
+    ///            hashmap!(
+    ///                City => "Berlin",
+    ///                Country => "Deutschland",
+    ///                CountryCode => "DE",
+    ///                HouseNumber => "77",
+    ///                Postcode => "10117",
+    ///                Road => "Unter den Linden",
+    ///            ),
+    ///            Default::default(),
+    ///        )
+    ///        .unwrap();
+    ///
+    ///    assert_eq!(
+    ///        json_ld,
+    ///        serde_json::json!({
+    ///            "@context": "https://schema.org",
+    ///            "@type": "PostalAddress",
+    ///            "streetAddress": "Unter den Linden 77",
+    ///            "addressLocality": "Berlin",
+    ///            "postalCode": "10117",
+    ///            "addressCountry": "DE",
+    ///        })
+    ///    );
+    /// # }
+    /// ```
+    pub fn postal_address_json_ld(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<Value, Error> {
+        let addr = into_addr.into();
+        let first = |components: &[Component]| {
+            components
+                .iter()
+                .filter_map(|c| addr[*c].as_ref())
+                .map(|v| v.trim())
+                .find(|v| !v.is_empty())
+                .map(|v| v.to_owned())
+        };
+        let country = conf
+            .country_code
+            .clone()
+            .or_else(|| first(&[Component::CountryCode]))
+            .map(|c| c.to_uppercase())
+            .or_else(|| first(&[Component::Country]));
+
+        let mut json_ld = Map::new();
+        json_ld.insert("@context".into(), "https://schema.org".into());
+        json_ld.insert("@type".into(), "PostalAddress".into());
+        let properties = [
+            ("streetAddress", Some(self.street(&addr, &conf)?)),
+            (
+                "addressLocality",
+                first(&[Component::City, Component::Town, Component::Village]),
+            ),
+            (
+                "addressRegion",
+                first(&[Component::State, Component::StateCode]),
+            ),
+            ("postalCode", first(&[Component::Postcode])),
+            ("addressCountry", country),
+        ];
+        for (name, value) in properties.iter() {
+            if let Some(value) = value.as_ref().filter(|v| !v.is_empty()) {
+                json_ld.insert(name.to_string(), value.clone().into());
+            }
+        }
+        Ok(Value::Object(json_ld))
+    }
+
+    /// format a [`Place`](struct.Place.html) as a [`h-adr`](http://microformats.org/wiki/h-adr) HTML fragment.
+    ///
+    /// The lines are the ones of the formatted place (separated by `<br>`),
+    /// each component being wrapped in a `<span>` with its microformat class.
+    /// The house number and the road make a single `p-street-address` when they are next to each other.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate maplit;
+    /// # fn main() {
+    ///    use address_formatter::Component::*;
+    ///
+    ///    let html = address_formatter::FORMATTER
+    ///        .format_h_adr(
+    ///            hashmap!(
+    ///                City => "Toulouse",
+    ///                Country => "France",
+    ///                CountryCode => "FR",
+    ///                HouseNumber => "17",
+    ///                Postcode => "31000",
+    ///                Road => "Rue du Médecin-Colonel Calbairac",
+    ///            ),
+    ///            Default::default(),
+    ///        )
+    ///        .unwrap();
+    ///
+    ///    assert_eq!(
+    ///        html,
+    ///        concat!(
+    ///            r#"<div class="h-adr">"#,
+    ///            r#"<span class="p-street-address">17 Rue du Médecin-Colonel Calbairac</span><br>"#,
+    ///            r#"<span class="p-postal-code">31000</span> <span class="p-locality">Toulouse</span><br>"#,
+    ///            r#"<span class="p-country-name">France</span>"#,
+    ///            "</div>"
+    ///        )
+    ///    );
+    /// # }
+    /// ```
+    pub fn format_h_adr(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<String, Error> {
+        let annotated = self.format_annotated_with_config(into_addr, conf)?;
+        let text = &annotated.text;
+
+        // the classes of the spans, the neighbour spans with the same class being merged
+        // (like the house number and the road)
+        let mut spans: Vec<(&str, std::ops::Range<usize>)> = vec![];
+        for (component, range) in &annotated.spans {
+            let class = match h_adr_class(*component) {
+                Some(class) => class,
+                None => continue,
+            };
+            if let Some((last_class, last_range)) = spans.last_mut() {
+                let between = &text[last_range.end..range.start];
+                if *last_class == class && !between.contains('\n') && between.trim().is_empty() {
+                    last_range.end = range.end;
+                    continue;
+                }
+            }
+            spans.push((class, range.clone()));
+        }
+
+        let mut html = r#"<div class="h-adr">"#.to_owned();
+        let mut last = 0;
+        for (class, range) in spans {
+            escape_html(&text[last..range.start], &mut html);
+            html.push_str(&format!(r#"<span class="{}">"#, class));
+            escape_html(&text[range.clone()], &mut html);
+            html.push_str("</span>");
+            last = range.end;
+        }
+        escape_html(&text[last..], &mut html);
+        let html = html.trim_end().replace('\n', "<br>");
+        Ok(html + "</div>")
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{Component, Place, PlaceBuilder};
+use failure::{format_err, Error};
+use serde_json::{Map, Value};
+
+/// the ISO 3166-2 codes of the administrative levels in the jsonv2 `address` object, and their component
+/// (the level 4 being the states or regions, and the level 6 the counties or departments)
+const ISO3166_2_LEVELS: [(&str, Component); 2] = [
+    ("ISO3166-2-lvl4", Component::StateCode),
+    ("ISO3166-2-lvl6", Component::CountyCode),
+];
+
+/// the geocodejson properties not named like the components (or with another meaning than their alias)
+const GEOCODEJSON_KEYS: [(&str, &str); 3] = [
+    ("housenumber", "house_number"),
+    // a named place smaller than a city (like a hamlet), displayed when there is no city
+    ("locality", "village"),
+    // a subdivision of a city
+    ("district", "city_district"),
+];
+
+impl PlaceBuilder {
+    /// Build a [`Place`](struct.Place.html) from a [Nominatim](https://nominatim.org/release-docs/latest/api/Output/)
+    /// result of a reverse or a search request.
+    ///
+    /// The result can be in the `json`/`jsonv2` format (with the `address` requested with `addressdetails=1`)
+    /// or be a feature of the `geocodejson` format.
+    /// Without the `address`, the `display_name` is used as the road (like an OSM `addr:full`).
+    ///
+    /// The ISO 3166-2 codes of the administrative levels 4 and 6 are used as state and county codes,
+    /// and the name of the feature is put in the `Attention` field if it is not already a component
+    /// (like for a street or a city).
+    ///
+    /// ```
+    /// let result = serde_json::json!({
+    ///     "category": "amenity",
+    ///     "type": "townhall",
+    ///     "name": "Mairie de Toulouse",
+    ///     "display_name": "Mairie de Toulouse, Place du Capitole, Toulouse, 31000, France",
+    ///     "address": {
+    ///         "amenity": "Mairie de Toulouse",
+    ///         "road": "Place du Capitole",
+    ///         "city": "Toulouse",
+    ///         "ISO3166-2-lvl4": "FR-OCC",
+    ///         "postcode": "31000",
+    ///         "country": "France",
+    ///         "country_code": "fr"
+    ///     }
+    /// });
+    /// let place = address_formatter::PlaceBuilder::default()
+    ///     .build_place_from_nominatim(&result)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     address_formatter::FORMATTER.format(place).unwrap(),
+    ///     "Mairie de Toulouse\nPlace du Capitole\n31000 Toulouse\nFrance\n"
+    /// );
+    /// ```
+    pub fn build_place_from_nominatim(&self, result: &Value) -> Result<Place, Error> {
+        let result = result
+            .as_object()
+            .ok_or_else(|| format_err!("a Nominatim result should be a JSON object"))?;
+        if result.get("type").and_then(Value::as_str) == Some("Feature") {
+            let properties = result
+                .get("properties")
+                .and_then(|p| p.get("geocoding"))
+                .and_then(Value::as_object)
+                .ok_or_else(|| {
+                    format_err!("no 'properties.geocoding' in the geocodejson feature")
+                })?;
+            Ok(self.build_place_from_geocodejson(properties))
+        } else {
+            match result.get("address").and_then(Value::as_object) {
+                Some(address) => Ok(self.build_place_from_jsonv2(result, address)),
+                None => {
+                    // without the address details, only the display name describes the address
+                    // (like an OSM addr:full)
+                    let display_name = result
+                        .get("display_name")
+                        .and_then(Value::as_str)
+                        .filter(|n| !n.trim().is_empty())
+                        .ok_or_else(|| {
+                            format_err!(
+                                "neither 'address' nor 'display_name' in the Nominatim result, \
+                                 the address should be requested with addressdetails=1"
+                            )
+                        })?;
+                    let mut place = Place::default();
+                    place[Component::Road] = Some(display_name.trim().to_owned());
+                    Ok(place)
+                }
+            }
+        }
+    }
+
+    /// Build the [`Place`](struct.Place.html)s of all the results of a Nominatim search request,
+    /// either an array of `json`/`jsonv2` results or a `geocodejson` feature collection.
+    ///
+    /// See [`build_place_from_nominatim`](struct.PlaceBuilder.html#method.build_place_from_nominatim)
+    pub fn build_places_from_nominatim(&self, response: &Value) -> Result<Vec<Place>, Error> {
+        let results = match response {
+            Value::Array(results) => results,
+            _ => response
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or_else(|| {
+                    format_err!("a Nominatim response should be an array or a feature collection")
+                })?,
+        };
+        results
+            .iter()
+            .map(|r| self.build_place_from_nominatim(r))
+            .collect()
+    }
+
+    fn build_place_from_jsonv2(
+        &self,
+        result: &Map<String, Value>,
+        address: &Map<String, Value>,
+    ) -> Place {
+        let values = address
+            .iter()
+            .filter(|(k, _)| self.is_known(k))
+            .filter_map(|(k, v)| v.as_str().map(|v| (k.as_str(), v.to_owned())));
+        let mut place = self.build_place(values);
+
+        for (key, component) in &ISO3166_2_LEVELS {
+            if let Some(code) = address.get(*key).and_then(Value::as_str) {
+                if place[*component].is_none() {
+                    // the code is prefixed by the country code (like "US-NY")
+                    let code = code.splitn(2, '-').last().unwrap_or(code);
+                    place[*component] = Some(code.to_owned());
+                }
+            }
+        }
+
+        // the name of the feature is either given, or in the address under its type (like "amenity": "Mairie")
+        let name = result.get("name").and_then(Value::as_str).or_else(|| {
+            ["addresstype", "category", "class", "type"]
+                .iter()
+                .filter_map(|k| result.get(*k).and_then(Value::as_str))
+                .filter(|t| !self.is_known(t))
+                .find_map(|t| address.get(t).and_then(Value::as_str))
+        });
+        finish_place(place, name)
+    }
+
+    fn build_place_from_geocodejson(&self, properties: &Map<String, Value>) -> Place {
+        let values = properties
+            .iter()
+            .filter_map(|(k, v)| {
+                let k = GEOCODEJSON_KEYS
+                    .iter()
+                    .find(|(key, _)| key == k)
+                    .map(|(_, component)| *component)
+                    .unwrap_or(k.as_str());
+                match v {
+                    Value::String(v) if self.is_known(k) => Some((k, v.clone())),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        let place = self.build_place(values);
+        finish_place(place, properties.get("name").and_then(Value::as_str))
+    }
+}
+
+/// set the feature's name as `Attention` and uppercase the country code
+fn finish_place(mut place: Place, name: Option<&str>) -> Place {
+    if let Some(name) = name.filter(|n| !n.trim().is_empty()) {
+        let already_used = place.values().any(|v| v.as_deref() == Some(name));
+        if !already_used && place[Component::Attention].is_none() {
+            place[Component::Attention] = Some(name.to_owned());
+        }
+    }
+    if let Some(country_code) = &mut place[Component::CountryCode] {
+        *country_code = country_code.to_uppercase();
+    }
+    place
+}
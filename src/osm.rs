@@ -0,0 +1,112 @@
+use crate::{Component, Place, PlaceBuilder};
+use std::collections::HashMap;
+use strum::IntoEnumIterator;
+
+/// prefix of the OpenStreetMap address tags
+const ADDR_PREFIX: &str = "addr:";
+
+/// the `addr:*` tags not named like the components (or their aliases)
+const OSM_KEYS: [(&str, &str); 4] = [
+    ("housenumber", "house_number"),
+    ("housename", "house"),
+    ("country", "country_code"),
+    ("district", "city_district"),
+];
+
+impl PlaceBuilder {
+    /// Build a [`Place`](struct.Place.html) from the tags of an [OpenStreetMap](https://wiki.openstreetmap.org/wiki/Key:addr)
+    /// node, way or relation.
+    ///
+    /// The `addr:*` tags are used (like `addr:housenumber`, `addr:street` or `addr:city`), the others being ignored, except:
+    ///  * `name`, used as `Attention`
+    ///  * `addr:place`, used as the road when there is no `addr:street` (for the addresses attached to a place)
+    ///  * `addr:unit` (like an apartment or a suite), added to the house: there is no unit component,
+    ///    and the house is written on its own line next to the road (it is the `line2` of the
+    ///    [`AddressLines`](struct.AddressLines.html), where the shipping APIs expect the unit)
+    ///  * `addr:full`, used as a fallback when there are no structured address tags
+    ///    (other than the country)
+    ///
+    /// ```
+    /// let tags = vec![
+    ///     ("amenity", "townhall"),
+    ///     ("name", "Mairie de Toulouse"),
+    ///     ("addr:street", "Place du Capitole"),
+    ///     ("addr:city", "Toulouse"),
+    ///     ("addr:postcode", "31000"),
+    ///     ("addr:country", "FR"),
+    /// ];
+    /// let place = address_formatter::PlaceBuilder::default().build_place_from_osm_tags(tags);
+    ///
+    /// assert_eq!(
+    ///     address_formatter::FORMATTER.format(place).unwrap(),
+    ///     "Mairie de Toulouse\nPlace du Capitole\n31000 Toulouse\n"
+    /// );
+    /// ```
+    pub fn build_place_from_osm_tags<'a>(
+        &self,
+        tags: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Place {
+        let mut addr_tags = HashMap::new();
+        let mut name = None;
+        for (k, v) in tags {
+            let v = v.trim();
+            if v.is_empty() {
+                continue;
+            }
+            if k == "name" {
+                name = Some(v);
+            } else if let Some(addr_key) = k.strip_prefix(ADDR_PREFIX) {
+                addr_tags.insert(addr_key, v);
+            }
+        }
+
+        let mut values = vec![];
+        for (k, v) in &addr_tags {
+            let k = OSM_KEYS
+                .iter()
+                .find(|(key, _)| key == k)
+                .map(|(_, component)| *component)
+                .unwrap_or(k);
+            if self.is_known(k) {
+                values.push((k, v.to_string()));
+            }
+        }
+        let mut place = self.build_place(values);
+
+        if let Some(addr_place) = addr_tags.get("place") {
+            // the address is attached to a place (like a hamlet or a square) instead of a street
+            let component = if place[Component::Road].is_none() {
+                Component::Road
+            } else {
+                Component::Neighbourhood
+            };
+            if place[component].is_none() {
+                place[component] = Some(addr_place.to_string());
+            }
+        }
+        if let Some(unit) = addr_tags.get("unit") {
+            place[Component::House] = Some(match &place[Component::House] {
+                Some(house) => format!("{}, {}", house, unit),
+                None => unit.to_string(),
+            });
+        }
+        if let Some(full) = addr_tags.get("full") {
+            // the country alone does not describe the address
+            let structured = Component::iter()
+                .filter(|c| *c != Component::CountryCode && *c != Component::Country)
+                .any(|c| place[c].is_some());
+            if !structured {
+                place[Component::Road] = Some(full.to_string());
+            }
+        }
+        if let Some(country_code) = &mut place[Component::CountryCode] {
+            *country_code = country_code.to_uppercase();
+        }
+        if let Some(name) = name {
+            if place[Component::Attention].is_none() {
+                place[Component::Attention] = Some(name.to_owned());
+            }
+        }
+        place
+    }
+}
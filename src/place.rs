@@ -1,5 +1,11 @@
+use crate::PlaceBuilder;
 use enum_map::{Enum, EnumMap};
-use serde::Serialize;
+use itertools::Itertools;
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
 /// A `Component` is a field of a [`Place`](struct.Place.html)
@@ -59,11 +65,30 @@ impl serde::Serialize for Component {
     }
 }
 
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Component::from_str(&s).map_err(|_| serde::de::Error::custom(unknown_component(&s)))
+    }
+}
+
+/// error message of an unknown component name
+fn unknown_component(name: &str) -> String {
+    format!(
+        "unknown component `{}`, expected one of {}",
+        name,
+        Component::iter().map(|c| format!("`{}`", c)).join(", ")
+    )
+}
+
 /// A [`Place`](struct.Place.html) is a structured way to represent a postal address.
 ///
 ///
 /// Note: it is internally represented as an EnumMap to easily loop over all the fields
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Serialize)]
 pub struct Place(EnumMap<Component, Option<String>>);
 
 impl std::ops::Deref for Place {
@@ -90,3 +115,254 @@ where
         a
     }
 }
+
+/// A [`Place`](struct.Place.html) whose values can be borrowed from the caller's data,
+/// to [`format_into`](struct.Formatter.html#method.format_into) a buffer without copying them.
+///
+/// The values are only copied when the formatting needs to change them (like for an abbreviation).
+///
+/// ```
+/// use address_formatter::{BorrowedPlace, Component};
+///
+/// let road = String::from("Rue du Médecin-Colonel Calbairac");
+/// let place: BorrowedPlace = vec![
+///     (Component::Road, road.as_str()),
+///     (Component::City, "Toulouse"),
+///     (Component::CountryCode, "FR"),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// let mut text = String::new();
+/// address_formatter::FORMATTER
+///     .format_into(place, &Default::default(), &mut text)
+///     .unwrap();
+/// assert_eq!(text, "Rue du Médecin-Colonel Calbairac\nToulouse\n");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct BorrowedPlace<'a>(EnumMap<Component, Option<Cow<'a, str>>>);
+
+impl<'a> std::ops::Deref for BorrowedPlace<'a> {
+    type Target = EnumMap<Component, Option<Cow<'a, str>>>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<'a> std::ops::DerefMut for BorrowedPlace<'a> {
+    fn deref_mut(&mut self) -> &mut EnumMap<Component, Option<Cow<'a, str>>> {
+        &mut self.0
+    }
+}
+
+impl<'a> BorrowedPlace<'a> {
+    /// the same place, bound to a shorter lifetime (to add values borrowed from the formatter)
+    pub(crate) fn shorten<'b>(self) -> BorrowedPlace<'b>
+    where
+        'a: 'b,
+    {
+        let mut place = BorrowedPlace::default();
+        for (c, v) in self.0 {
+            place[c] = v;
+        }
+        place
+    }
+}
+
+impl<'a> From<&'a Place> for BorrowedPlace<'a> {
+    fn from(place: &'a Place) -> Self {
+        let mut borrowed = Self::default();
+        for (c, v) in place.iter() {
+            borrowed[c] = v.as_deref().map(Cow::Borrowed);
+        }
+        borrowed
+    }
+}
+
+impl From<Place> for BorrowedPlace<'static> {
+    fn from(place: Place) -> Self {
+        let mut owned = Self::default();
+        for (c, v) in place.0 {
+            owned[c] = v.map(Cow::Owned);
+        }
+        owned
+    }
+}
+
+impl<'a> From<BorrowedPlace<'a>> for Place {
+    fn from(place: BorrowedPlace<'a>) -> Self {
+        let mut owned = Self::default();
+        for (c, v) in place.0 {
+            owned[c] = v.map(Cow::into_owned);
+        }
+        owned
+    }
+}
+
+impl<'a> std::iter::FromIterator<(Component, &'a str)> for BorrowedPlace<'a> {
+    fn from_iter<T: IntoIterator<Item = (Component, &'a str)>>(data: T) -> Self {
+        let mut place = Self::default();
+        for (k, v) in data {
+            place[k] = Some(Cow::Borrowed(v));
+        }
+        place
+    }
+}
+
+/// the value of a component, the numbers (like a house number or a postcode) being accepted too
+struct ComponentValue(Option<String>);
+
+impl<'de> Deserialize<'de> for ComponentValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = ComponentValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a string, a number or null")
+            }
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(ComponentValue(Some(v.to_owned())))
+            }
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+                Ok(ComponentValue(Some(v)))
+            }
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(ComponentValue(Some(v.to_string())))
+            }
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(ComponentValue(Some(v.to_string())))
+            }
+            fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(ComponentValue(Some(v.to_string())))
+            }
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(ComponentValue(None))
+            }
+            fn visit_none<E>(self) -> Result<Self::Value, E> {
+                Ok(ComponentValue(None))
+            }
+            fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                ComponentValue::deserialize(deserializer)
+            }
+        }
+
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+/// read a map of values, the keys being given to a function
+struct PlaceVisitor<F>(F);
+
+impl<'de, F> Visitor<'de> for PlaceVisitor<F>
+where
+    F: FnOnce(Vec<(String, String)>) -> Result<Place, String>,
+{
+    type Value = Place;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a map of the place's components")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut values = vec![];
+        while let Some((k, v)) = map.next_entry::<String, ComponentValue>()? {
+            if let Some(v) = v.0 {
+                values.push((k, v));
+            }
+        }
+        (self.0)(values).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserialize a [`Place`](struct.Place.html) from a map keyed by the snake_case component names
+/// (like the one given by its serialization).
+///
+/// The values can be strings, numbers or null, and an unknown key is an error.
+/// To accept the aliases of the components (like `street`), use
+/// [`deserialize_place_with_aliases`](fn.deserialize_place_with_aliases.html).
+///
+/// ```
+/// let place: address_formatter::Place = serde_json::from_str(
+///     r#"{"road": "Rue du Médecin-Colonel Calbairac", "house_number": 17, "country_code": "FR"}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     place[address_formatter::Component::HouseNumber],
+///     Some("17".to_owned())
+/// );
+/// ```
+impl<'de> Deserialize<'de> for Place {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(PlaceVisitor(|values: Vec<(String, String)>| {
+            let mut place = Place::default();
+            for (k, v) in values {
+                let component = Component::from_str(&k).map_err(|_| unknown_component(&k))?;
+                place[component] = Some(v);
+            }
+            Ok(place)
+        }))
+    }
+}
+
+/// Deserialize a [`Place`](struct.Place.html) with a [`PlaceBuilder`](struct.PlaceBuilder.html),
+/// so the aliases of the components are used, like in
+/// [`build_place`](struct.PlaceBuilder.html#method.build_place).
+impl<'de> DeserializeSeed<'de> for &PlaceBuilder {
+    type Value = Place;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Place, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(PlaceVisitor(|values: Vec<(String, String)>| {
+            Ok(self.build_place(values.iter().map(|(k, v)| (k.as_str(), v.clone()))))
+        }))
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PLACE_BUILDER: PlaceBuilder = PlaceBuilder::default();
+}
+
+/// Deserialize a [`Place`](struct.Place.html) from a map keyed by the component names or their aliases
+/// (like `street` or `province`), like [`PlaceBuilder::build_place`](struct.PlaceBuilder.html#method.build_place)
+/// with the embedded opencage configuration.
+///
+/// It can be used with `#[serde(deserialize_with = "address_formatter::deserialize_place_with_aliases")]`
+///
+/// ```
+/// #[derive(serde::Deserialize)]
+/// struct Poi {
+///     name: String,
+///     #[serde(deserialize_with = "address_formatter::deserialize_place_with_aliases")]
+///     address: address_formatter::Place,
+/// }
+///
+/// let poi: Poi = serde_json::from_str(
+///     r#"{"name": "Mairie", "address": {"street": "Rue des Remparts", "city": "Papeete"}}"#,
+/// )
+/// .unwrap();
+/// assert_eq!(
+///     poi.address[address_formatter::Component::Road],
+///     Some("Rue des Remparts".to_owned())
+/// );
+/// ```
+pub fn deserialize_place_with_aliases<'de, D>(deserializer: D) -> Result<Place, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    (&*PLACE_BUILDER).deserialize(deserializer)
+}
@@ -1,234 +1,602 @@
+use crate::error::{ConfigurationError, ConfigurationIssue};
 use crate::formatter::{
     CountryCode, Formatter, NewComponent, PlaceBuilder, ReplaceRule, Replacement, Rules, Template,
     Templates,
 };
 use crate::Component;
-use failure::{format_err, Error};
+use include_dir::{include_dir, include_dir_impl};
 use std::collections::HashMap;
+use std::path::Path;
 use std::str::FromStr;
+use yaml_rust::Yaml;
 
-pub fn read_configuration() -> Formatter {
-    // read all the opencage configuration
-    // let opencage_dir = include_dir!("./address-formatting/conf");
-    let templates_file = include_str!("../address-formatting/conf/countries/worldwide.yaml");
+const WORLDWIDE_FILE: &str = "countries/worldwide.yaml";
+const STATE_CODES_FILE: &str = "state_codes.yaml";
+const COUNTY_CODES_FILE: &str = "county_codes.yaml";
+const COUNTRY_TO_LANG_FILE: &str = "country2lang.yaml";
+const ABBREVIATIONS_DIR: &str = "abbreviations";
+const COMPONENTS_FILE: &str = "components.yaml";
 
-    let raw_templates = yaml_rust::YamlLoader::load_from_str(templates_file)
-        .expect("impossible to read worldwide.yaml file");
-    let default_template = build_template(&raw_templates[0]["default"]["address_template"])
-        .expect("no default address_template provided");
-    let fallback_template = build_template(&raw_templates[0]["default"]["fallback_template"])
-        .expect("no fallback address_template provided");
+/// Content of the opencage configuration files needed to build a [`Formatter`](struct.Formatter.html)
+pub struct FormatterFiles {
+    pub worldwide: String,
+    pub state_codes: String,
+    pub county_codes: String,
+    pub country_to_lang: String,
+    /// content of the abbreviations files, by language
+    pub abbreviations: Vec<(String, String)>,
+}
+
+impl FormatterFiles {
+    /// the opencage configuration embedded in the binary
+    pub fn embedded() -> Self {
+        let abbreviations_dir = include_dir!("./address-formatting/conf/abbreviations");
+        FormatterFiles {
+            worldwide: include_str!("../address-formatting/conf/countries/worldwide.yaml")
+                .to_owned(),
+            state_codes: include_str!("../address-formatting/conf/state_codes.yaml").to_owned(),
+            county_codes: include_str!("../address-formatting/conf/county_codes.yaml").to_owned(),
+            country_to_lang: include_str!("../address-formatting/conf/country2lang.yaml")
+                .to_owned(),
+            abbreviations: abbreviations_dir
+                .files()
+                .iter()
+                .filter_map(|f| {
+                    Some((
+                        f.path().file_stem()?.to_str()?.to_owned(),
+                        f.contents_utf8()?.to_owned(),
+                    ))
+                })
+                .collect(),
+        }
+    }
+
+    /// read the opencage configuration from a directory
+    /// (the `conf` directory of the opencage repository)
+    pub fn from_dir(config_dir: &Path) -> Result<Self, ConfigurationError> {
+        let mut issues = vec![];
+        let mut abbreviations = vec![];
+        match std::fs::read_dir(config_dir.join(ABBREVIATIONS_DIR)) {
+            Ok(entries) => {
+                for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                    if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
+                        continue;
+                    }
+                    if let Some(lang) = path.file_stem().and_then(|l| l.to_str()) {
+                        let file = format!("{}/{}.yaml", ABBREVIATIONS_DIR, lang);
+                        if let Some(content) = read_file(config_dir, &file, &mut issues) {
+                            abbreviations.push((lang.to_owned(), content));
+                        }
+                    }
+                }
+            }
+            Err(e) => issues.push(issue(ABBREVIATIONS_DIR, None, None, e)),
+        }
+        let worldwide = read_file(config_dir, WORLDWIDE_FILE, &mut issues);
+        let state_codes = read_file(config_dir, STATE_CODES_FILE, &mut issues);
+        let county_codes = read_file(config_dir, COUNTY_CODES_FILE, &mut issues);
+        let country_to_lang = read_file(config_dir, COUNTRY_TO_LANG_FILE, &mut issues);
+
+        match (worldwide, state_codes, county_codes, country_to_lang) {
+            (Some(worldwide), Some(state_codes), Some(county_codes), Some(country_to_lang))
+                if issues.is_empty() =>
+            {
+                Ok(FormatterFiles {
+                    worldwide,
+                    state_codes,
+                    county_codes,
+                    country_to_lang,
+                    abbreviations,
+                })
+            }
+            _ => Err(ConfigurationError { issues }),
+        }
+    }
+}
+
+fn issue(
+    file: &str,
+    country: Option<&str>,
+    rule: Option<&str>,
+    message: impl ToString,
+) -> ConfigurationIssue {
+    ConfigurationIssue {
+        file: file.to_owned(),
+        country: country.map(|c| c.to_owned()),
+        rule: rule.map(|r| r.to_owned()),
+        message: message.to_string(),
+    }
+}
+
+fn read_file(
+    config_dir: &Path,
+    file: &str,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> Option<String> {
+    std::fs::read_to_string(config_dir.join(file))
+        .map_err(|e| issues.push(issue(file, None, None, e)))
+        .ok()
+}
+
+fn load_yaml(file: &str, content: &str, issues: &mut Vec<ConfigurationIssue>) -> Vec<Yaml> {
+    yaml_rust::YamlLoader::load_from_str(content)
+        .map_err(|e| issues.push(issue(file, None, None, e)))
+        .unwrap_or_default()
+}
+
+pub fn read_configuration(files: &FormatterFiles) -> Result<Formatter, ConfigurationError> {
+    let mut issues = vec![];
+
+    let templates = read_templates(&files.worldwide, &mut issues);
+    let state_codes = read_codes(STATE_CODES_FILE, &files.state_codes, &mut issues);
+    let county_codes = read_codes(COUNTY_CODES_FILE, &files.county_codes, &mut issues);
+    let country_to_lang = read_country_to_lang(&files.country_to_lang, &mut issues);
+    let abbreviations = files
+        .abbreviations
+        .iter()
+        .map(|(lang, abbreviations_file)| {
+            let file = format!("{}/{}.yaml", ABBREVIATIONS_DIR, lang);
+            let rules = load_yaml(&file, abbreviations_file, &mut issues)
+                .iter()
+                .flat_map(|a| read_abbreviations(&file, a, &mut issues))
+                .collect();
+            (lang.clone(), rules)
+        })
+        .collect();
+
+    match templates {
+        Some(templates) if issues.is_empty() => Ok(Formatter {
+            templates,
+            state_codes,
+            county_codes,
+            country_to_lang,
+            abbreviations,
+        }),
+        _ => Err(ConfigurationError { issues }),
+    }
+}
+
+fn read_templates(worldwide: &str, issues: &mut Vec<ConfigurationIssue>) -> Option<Templates> {
+    let raw_templates = load_yaml(WORLDWIDE_FILE, worldwide, issues);
+    let raw_templates = match raw_templates.first() {
+        Some(t) => t,
+        None => {
+            issues.push(issue(WORLDWIDE_FILE, None, None, "no templates defined"));
+            return None;
+        }
+    };
+    // most of the countries use one of the generic templates, they are compiled only once
+    let mut compiled = HashMap::new();
+    let default_template = read_template(
+        &raw_templates["default"],
+        "address_template",
+        None,
+        &mut compiled,
+        issues,
+    );
+    let fallback_template = read_template(
+        &raw_templates["default"],
+        "fallback_template",
+        None,
+        &mut compiled,
+        issues,
+    );
 
     // some countries uses the same rules as other countries (with some slight changes)
     // they are marked as `use_country: another_country_code`
     // we store them separatly first, to be able to create fully built templates
-    let mut overrided_countries = HashMap::new();
+    let mut overrided_countries = vec![];
 
     let mut fallback_templates_by_country = HashMap::new();
     let mut rules_by_country = HashMap::new();
-    let mut templates_by_country: HashMap<CountryCode, Template> = raw_templates[0]
+    let mut templates_by_country = HashMap::new();
+
+    let countries = raw_templates
         .as_hash()
-        .unwrap()
-        .iter()
+        .into_iter()
+        .flatten()
         .filter_map(|(k, v)| {
             k.as_str()
                 .and_then(|k| CountryCode::from_str(k).ok())
                 .map(|c| (c, v))
-        })
-        .filter_map(|(country_code, v)| {
-            if let Ok(fallback_template) = build_template(&v["fallback_template"]) {
+        });
+    for (country_code, v) in countries {
+        let country = Some(country_code.as_str());
+        if !v["fallback_template"].is_badvalue() {
+            if let Some(fallback_template) =
+                read_template(v, "fallback_template", country, &mut compiled, issues)
+            {
                 fallback_templates_by_country.insert(country_code.clone(), fallback_template);
             }
-            if let Some(parent_country) = v["use_country"]
-                .as_str()
-                .and_then(|k| CountryCode::from_str(k).ok())
-            {
+        }
+        if let Some(parent_country) = v["use_country"].as_str() {
+            match CountryCode::from_str(parent_country) {
                 // we store it for later processing
-                overrided_countries.insert(country_code, (parent_country, v.clone()));
-                None
-            } else {
-                let replace_rules = read_replace(&v["replace"]);
-                let post_format_replace_rules = read_replace(&v["postformat_replace"])
-                    .into_iter()
-                    .map(|r| match r {
-                        ReplaceRule::All(r) => r,
-                        _ => panic!("postformat rules cannot be applied on only one element"),
-                    })
-                    .collect();
-
-                let template = build_template(&v["address_template"]).expect(&format!(
-                    "no address_template found for country {}",
-                    country_code
+                Ok(parent_country) => overrided_countries.push((country_code, parent_country, v)),
+                Err(e) => issues.push(issue(WORLDWIDE_FILE, country, Some("use_country"), e)),
+            }
+            continue;
+        }
+        let replace_rules = read_replace(&v["replace"], "replace", country, issues);
+        let post_format_replace_rules = read_replace(
+            &v["postformat_replace"],
+            "postformat_replace",
+            country,
+            issues,
+        )
+        .into_iter()
+        .filter_map(|r| match r {
+            ReplaceRule::All(r) => Some(r),
+            ReplaceRule::Component((c, r)) => {
+                issues.push(issue(
+                    WORLDWIDE_FILE,
+                    country,
+                    Some(&format!("postformat_replace: {}={}", c, r.regex)),
+                    "postformat rules cannot be applied on only one element",
                 ));
-                let rules = Rules {
-                    replace: replace_rules,
-                    postformat_replace: post_format_replace_rules,
-                    ..Default::default()
-                };
-                rules_by_country.insert(country_code.clone(), rules);
-
-                Some((country_code, template))
+                None
             }
         })
         .collect();
 
-    for (country_code, (parent_country_code, template)) in overrided_countries.into_iter() {
-        let overrided_template = templates_by_country[&parent_country_code].clone();
-        templates_by_country.insert(country_code.clone(), overrided_template);
+        if let Some(template) = read_template(v, "address_template", country, &mut compiled, issues)
+        {
+            templates_by_country.insert(country_code.clone(), template);
+        }
+        let rules = Rules {
+            replace: replace_rules,
+            postformat_replace: post_format_replace_rules,
+            ..Default::default()
+        };
+        rules_by_country.insert(country_code, rules);
+    }
+
+    for (country_code, parent_country_code, template) in overrided_countries.into_iter() {
+        let country = Some(country_code.as_str());
+        let new_rules = match use_country(
+            &mut templates_by_country,
+            &mut rules_by_country,
+            &country_code,
+            &parent_country_code,
+        ) {
+            Ok(rules) => rules,
+            Err(e) => {
+                issues.push(issue(WORLDWIDE_FILE, country, Some("use_country"), e));
+                continue;
+            }
+        };
 
         let mut add_component = None;
         if let Some(ac) = template["add_component"].as_str() {
             let part: Vec<_> = ac.split('=').collect();
-            assert_eq!(part.len(), 2);
-            let component = Component::from_str(part[0]);
-            if let Ok(c) = component {
-                // the only valid component that can be added is 'state'
-                if c == Component::State {
-                    add_component = Some(NewComponent {
-                        component: c,
-                        new_value: part[1].to_owned(),
-                    });
+            if part.len() != 2 {
+                issues.push(issue(
+                    WORLDWIDE_FILE,
+                    country,
+                    Some(&format!("add_component: {}", ac)),
+                    "add_component should be written 'component=value'",
+                ));
+            } else {
+                match Component::from_str(part[0]) {
+                    // the only valid component that can be added is 'state'
+                    Ok(c) if c == Component::State => {
+                        add_component = Some(NewComponent {
+                            component: c,
+                            new_value: part[1].to_owned(),
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(_) => issues.push(issue(
+                        WORLDWIDE_FILE,
+                        country,
+                        Some(&format!("add_component: {}", ac)),
+                        format!("'{}' is not a valid component", part[0]),
+                    )),
                 }
             }
         }
-
-        let mut new_rules = rules_by_country
-            .get(&parent_country_code)
-            .map(|r| r.clone())
-            .unwrap_or_else(|| Rules::default());
-        new_rules.change_country_code = Some(parent_country_code.as_str().to_owned());
         new_rules.change_country = template["change_country"].as_str().map(|s| s.to_string());
         new_rules.add_component = add_component;
-        rules_by_country.insert(country_code.clone(), new_rules);
     }
 
-    let state_codes_file = include_str!("../address-formatting/conf/state_codes.yaml");
-    let state_codes: HashMap<String, HashMap<String, String>> =
-        serde_yaml::from_str(state_codes_file).expect("invalid state_codes.yaml file");
-    let state_codes = state_codes
-        .into_iter()
-        .flat_map(|(country, states)| {
-            states.into_iter().map(move |(state_code, state_name)| {
-                (
-                    (
-                        CountryCode::from_str(&country).expect("invalid country code"),
-                        state_name,
-                    ),
-                    state_code,
-                )
-            })
-        })
-        .collect();
-    let county_codes_file = include_str!("../address-formatting/conf/county_codes.yaml");
-    let county_codes: HashMap<String, HashMap<String, String>> =
-        serde_yaml::from_str(county_codes_file).expect("invalid county_codes.yaml file");
-    let county_codes = county_codes
-        .into_iter()
-        .flat_map(|(country, counties)| {
-            counties.into_iter().map(move |(county_code, county_name)| {
-                (
-                    (
-                        CountryCode::from_str(&country).expect("invalid country code"),
-                        county_name,
-                    ),
-                    county_code,
-                )
-            })
-        })
-        .collect();
-
-    let templates = Templates {
-        default_template,
-        fallback_template,
+    Some(Templates {
+        default_template: default_template?,
+        fallback_template: fallback_template?,
         templates_by_country,
         fallback_templates_by_country,
         rules_by_country,
         fallback_rules: Rules::default(),
-    };
-    Formatter {
-        templates,
-        state_codes,
-        county_codes,
+    })
+}
+
+/// make a country use the template and the rules of another country
+///
+/// the rules of the country are returned, so they can be completed
+pub fn use_country<'a>(
+    templates_by_country: &mut HashMap<CountryCode, Template>,
+    rules_by_country: &'a mut HashMap<CountryCode, Rules>,
+    country_code: &CountryCode,
+    parent_country_code: &CountryCode,
+) -> Result<&'a mut Rules, String> {
+    let overrided_template = templates_by_country
+        .get(parent_country_code)
+        .ok_or_else(|| {
+            format!(
+                "no address_template found for country {}",
+                parent_country_code
+            )
+        })?
+        .clone();
+    templates_by_country.insert(country_code.clone(), overrided_template);
+
+    let mut new_rules = rules_by_country
+        .get(parent_country_code)
+        .cloned()
+        .unwrap_or_default();
+    new_rules.change_country_code = Some(parent_country_code.as_str().to_owned());
+    rules_by_country.insert(country_code.clone(), new_rules);
+    Ok(rules_by_country
+        .get_mut(country_code)
+        .expect("the rules have just been inserted"))
+}
+
+/// read the state_codes.yaml or county_codes.yaml file
+fn read_codes(
+    file: &str,
+    content: &str,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> HashMap<CountryCode, HashMap<String, String>> {
+    let codes: HashMap<String, HashMap<String, String>> = serde_yaml::from_str(content)
+        .map_err(|e| issues.push(issue(file, None, None, e)))
+        .unwrap_or_default();
+    let mut res = HashMap::new();
+    for (country, codes) in codes {
+        match CountryCode::from_str(&country) {
+            Ok(country_code) => {
+                res.insert(
+                    country_code,
+                    codes.into_iter().map(|(code, name)| (name, code)).collect(),
+                );
+            }
+            Err(e) => issues.push(issue(file, Some(&country), None, e)),
+        }
     }
+    res
+}
+
+fn read_country_to_lang(
+    content: &str,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> HashMap<CountryCode, Vec<String>> {
+    let country_to_lang: HashMap<String, String> = serde_yaml::from_str(content)
+        .map_err(|e| issues.push(issue(COUNTRY_TO_LANG_FILE, None, None, e)))
+        .unwrap_or_default();
+    country_to_lang
+        .into_iter()
+        .filter_map(|(country, langs)| match CountryCode::from_str(&country) {
+            Ok(country_code) => Some((
+                country_code,
+                langs.split(',').map(|l| l.trim().to_owned()).collect(),
+            )),
+            Err(e) => {
+                issues.push(issue(COUNTRY_TO_LANG_FILE, Some(&country), None, e));
+                None
+            }
+        })
+        .collect()
+}
+
+/// the opencage components configuration embedded in the binary
+pub fn embedded_components_file() -> &'static str {
+    include_str!("../address-formatting/conf/components.yaml")
+}
+
+/// read the opencage components configuration from a directory
+pub fn read_components_file(config_dir: &Path) -> Result<String, ConfigurationError> {
+    let mut issues = vec![];
+    read_file(config_dir, COMPONENTS_FILE, &mut issues).ok_or(ConfigurationError { issues })
 }
 
-pub fn read_place_builder_configuration() -> PlaceBuilder {
-    let component_file = include_str!("../address-formatting/conf/components.yaml");
-    let raw_components = yaml_rust::YamlLoader::load_from_str(component_file)
-        .expect("impossible to read components.yaml file");
+pub fn read_place_builder_configuration(
+    component_file: &str,
+) -> Result<PlaceBuilder, ConfigurationError> {
+    let mut issues = vec![];
+    let raw_components = load_yaml(COMPONENTS_FILE, component_file, &mut issues);
     let mut component_aliases = HashMap::<_, _>::new();
 
     for c in &raw_components {
         if let Some(aliases) = c["aliases"].as_vec() {
-            let name = c["name"].as_str().unwrap();
-            let component =
-                Component::from_str(name).expect(&format!("{} is not a valid component", name));
+            let name = match c["name"].as_str() {
+                Some(name) => name,
+                None => {
+                    issues.push(issue(COMPONENTS_FILE, None, None, "component without name"));
+                    continue;
+                }
+            };
+            let component = match Component::from_str(name) {
+                Ok(component) => component,
+                Err(_) => {
+                    issues.push(issue(
+                        COMPONENTS_FILE,
+                        None,
+                        Some(name),
+                        format!("{} is not a valid component", name),
+                    ));
+                    continue;
+                }
+            };
             for a in aliases {
-                component_aliases
-                    .entry(component)
-                    .or_insert_with(|| vec![])
-                    .push(a.as_str().unwrap().to_string());
+                match a.as_str() {
+                    Some(a) => component_aliases
+                        .entry(component)
+                        .or_insert_with(Vec::new)
+                        .push(a.to_string()),
+                    None => issues.push(issue(
+                        COMPONENTS_FILE,
+                        None,
+                        Some(name),
+                        format!("invalid alias {:?}", a),
+                    )),
+                }
             }
         }
     }
 
-    PlaceBuilder { component_aliases }
+    if issues.is_empty() {
+        Ok(PlaceBuilder { component_aliases })
+    } else {
+        Err(ConfigurationError { issues })
+    }
 }
 
-fn build_template(yaml_value: &yaml_rust::Yaml) -> Result<Template, Error> {
-    let addr_template = yaml_value
+/// read a template, reusing the already compiled one if the same template has already been read
+fn read_template(
+    yaml: &Yaml,
+    template_name: &str,
+    country: Option<&str>,
+    compiled: &mut HashMap<String, Template>,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> Option<Template> {
+    let template = yaml[template_name]
         .as_str()
-        .ok_or_else(|| format_err!("no value to build template"))?;
-
-    Ok(Template::new(addr_template))
-}
-
-fn read_replace(yaml_rules: &yaml_rust::Yaml) -> Vec<ReplaceRule> {
-    yaml_rules
-        .as_vec()
-        .map(|v| {
-            v.iter()
-                .map(|r| {
-                    let r = r.as_vec().expect("replace should be a list");
-                    assert_eq!(r.len(), 2);
-
-                    let first_val = r[0].as_str().expect("invalid replace rule");
-                    if first_val.contains('=') {
-                        // it's a replace on only one component
-                        // the rules is written 'component=<string_to_replace'
-                        let parts = first_val.split('=').collect::<Vec<_>>();
-                        let component = Component::from_str(parts[0]).expect(&format!(
-                            "in replace '{}' is not a valid component",
-                            parts[0]
-                        ));
-                        ReplaceRule::Component((
-                            component,
-                            Replacement {
-                                regex: regex::RegexBuilder::new(parts[1])
-                                    .multi_line(true)
-                                    .build()
-                                    .expect("invalid regex"),
-                                replacement_value: r[1]
-                                    .as_str()
-                                    .expect("invalid replace rule")
-                                    .to_owned(),
-                            },
-                        ))
-                    } else {
-                        // it's a replace for all components
-                        ReplaceRule::All(Replacement {
-                            regex: regex::RegexBuilder::new(first_val)
-                                .multi_line(true)
-                                .build()
-                                .expect("invalid regex"),
-                            replacement_value: r[1]
-                                .as_str()
-                                .expect("invalid replace rule")
-                                .to_owned(),
-                        })
-                    }
-                })
-                .collect()
-        })
-        .unwrap_or_else(|| vec![])
+        .ok_or_else(|| format!("no {} provided", template_name))
+        .and_then(|t| match compiled.get(t) {
+            Some(template) => Ok(template.clone()),
+            None => {
+                let template = Template::new(t).map_err(|e| e.to_string())?;
+                compiled.insert(t.to_owned(), template.clone());
+                Ok(template)
+            }
+        });
+    template
+        .map_err(|e| issues.push(issue(WORLDWIDE_FILE, country, Some(template_name), e)))
+        .ok()
+}
+
+fn read_replace(
+    yaml_rules: &Yaml,
+    rule_name: &str,
+    country: Option<&str>,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> Vec<ReplaceRule> {
+    let mut rules = vec![];
+    for r in yaml_rules.as_vec().into_iter().flatten() {
+        let r = match r.as_vec().map(|r| (r.len(), r)) {
+            Some((2, r)) => r,
+            _ => {
+                issues.push(issue(
+                    WORLDWIDE_FILE,
+                    country,
+                    Some(rule_name),
+                    format!("{:?} should be a list of 2 elements", r),
+                ));
+                continue;
+            }
+        };
+        let (first_val, replacement_value) = match (r[0].as_str(), r[1].as_str()) {
+            (Some(f), Some(v)) => (f, v.to_owned()),
+            _ => {
+                issues.push(issue(
+                    WORLDWIDE_FILE,
+                    country,
+                    Some(rule_name),
+                    format!("invalid replace rule {:?}", r),
+                ));
+                continue;
+            }
+        };
+        match build_replace_rule(first_val, replacement_value) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => issues.push(issue(
+                WORLDWIDE_FILE,
+                country,
+                Some(&format!("{}: {}", rule_name, first_val)),
+                e,
+            )),
+        }
+    }
+    rules
+}
+
+/// build a replace rule
+///
+/// the pattern is either a regex, to replace the matches in all the components,
+/// or written 'component=<regex>' to replace only in one component
+pub fn build_replace_rule(pattern: &str, replacement_value: String) -> Result<ReplaceRule, String> {
+    let build_regex = |regex: &str| {
+        regex::RegexBuilder::new(regex)
+            .multi_line(true)
+            .build()
+            .map_err(|e| e.to_string())
+    };
+    if pattern.contains('=') {
+        // it's a replace on only one component
+        // the rules is written 'component=<string_to_replace'
+        let parts = pattern.split('=').collect::<Vec<_>>();
+        let component = Component::from_str(parts[0])
+            .map_err(|_| format!("'{}' is not a valid component", parts[0]))?;
+        Ok(ReplaceRule::Component((
+            component,
+            Replacement {
+                regex: build_regex(parts[1])?,
+                replacement_value,
+            },
+        )))
+    } else {
+        // it's a replace for all components
+        Ok(ReplaceRule::All(Replacement {
+            regex: build_regex(pattern)?,
+            replacement_value,
+        }))
+    }
+}
+
+/// read the abbreviations of a language
+///
+/// the abbreviations are grouped by component, and each abbreviation is turned into
+/// a replace rule on this component, only on whole words
+fn read_abbreviations(
+    file: &str,
+    yaml_abbreviations: &Yaml,
+    issues: &mut Vec<ConfigurationIssue>,
+) -> Vec<ReplaceRule> {
+    let mut rules = vec![];
+    for (component, abbreviations) in yaml_abbreviations.as_hash().into_iter().flatten() {
+        let component_name = component.as_str().unwrap_or_default();
+        let component = match Component::from_str(component_name) {
+            Ok(c) => c,
+            Err(_) => {
+                issues.push(issue(
+                    file,
+                    None,
+                    Some(component_name),
+                    format!("{:?} is not a valid component", component),
+                ));
+                continue;
+            }
+        };
+        for (long, short) in abbreviations.as_hash().into_iter().flatten() {
+            let (long, short) = match (long.as_str(), short.as_str()) {
+                (Some(long), Some(short)) => (long, short),
+                _ => {
+                    issues.push(issue(
+                        file,
+                        None,
+                        Some(component_name),
+                        format!("invalid abbreviation {:?}: {:?}", long, short),
+                    ));
+                    continue;
+                }
+            };
+            rules.push(ReplaceRule::Component((
+                component,
+                Replacement {
+                    regex: regex::Regex::new(&format!(r"(^|\s){}\b", regex::escape(long)))
+                        .expect("invalid regex"),
+                    replacement_value: format!("${{1}}{}", short.replace('$', "$$")),
+                },
+            )));
+        }
+    }
+    rules
 }
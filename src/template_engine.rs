@@ -0,0 +1,230 @@
+use crate::{BorrowedPlace, Component};
+use failure::{format_err, Error};
+use std::str::FromStr;
+
+/// An element of a compiled template
+#[derive(Debug, Clone)]
+enum Instruction {
+    /// a text written as is
+    Text(String),
+    /// the value of a component (`{{{road}}}`, or `{{road}}` for an html escaped value)
+    Value { component: Component, escape: bool },
+    /// a `{{#first}} a || b || c {{/first}}` block, rendered as its first non empty value
+    First(Vec<Instruction>),
+    /// a `{{#road}}...{{/road}}` section, rendered if the component has a value,
+    /// or a `{{^road}}...{{/road}}` inverted section, rendered if it has none
+    /// (the component being `None` for an unknown variable, which never has a value)
+    Section {
+        component: Option<Component>,
+        inverted: bool,
+        block: Vec<Instruction>,
+    },
+}
+
+/// A block being compiled, closed by a `{{/name}}`
+struct OpenBlock<'t> {
+    /// the opening expression (like `#first` or `^road`)
+    opening: &'t str,
+    name: &'t str,
+    instructions: Vec<Instruction>,
+}
+
+/// A template written in the subset of mustache used by the OpenCage templates,
+/// compiled once in a list of instructions.
+///
+/// The supported expressions are the component values (`{{{road}}}` or `{{road}}`),
+/// the `{{#first}}...{{/first}}` blocks, the sections (`{{#road}}...{{/road}}` and `{{^road}}...{{/road}}`)
+/// and the comments (`{{! ... }}`).
+/// The unknown variables are rendered as empty strings (like any missing value).
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CompiledTemplate {
+    instructions: Vec<Instruction>,
+}
+
+impl CompiledTemplate {
+    pub fn compile(template: &str) -> Result<Self, Error> {
+        // the blocks being compiled, the first one being the whole template
+        let mut blocks = vec![OpenBlock {
+            opening: "",
+            name: "",
+            instructions: vec![],
+        }];
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let current = &mut blocks
+                .last_mut()
+                .expect("there is always a block")
+                .instructions;
+            if start > 0 {
+                current.push(Instruction::Text(rest[..start].to_owned()));
+            }
+            rest = &rest[start..];
+
+            let (open, close) = if rest.starts_with("{{{") {
+                ("{{{", "}}}")
+            } else if rest.starts_with("{{!--") {
+                ("{{!--", "--}}")
+            } else {
+                ("{{", "}}")
+            };
+            let expression = rest[open.len()..]
+                .find(close)
+                .map(|e| &rest[open.len()..open.len() + e])
+                .ok_or_else(|| {
+                    format_err!(
+                        "unclosed expression '{}'",
+                        rest.lines().next().unwrap_or("")
+                    )
+                })?;
+            rest = &rest[open.len() + expression.len() + close.len()..];
+
+            let expression = expression.trim();
+            if close == "}}}" {
+                current.extend(value(expression, false)?);
+            } else if open == "{{!--" || expression.starts_with('!') {
+                // a comment
+            } else if expression.starts_with('#') || expression.starts_with('^') {
+                let name = identifier(expression[1..].trim(), expression)?;
+                if expression.starts_with('^') && name == "first" {
+                    return Err(format_err!("unsupported expression '{}'", expression));
+                }
+                blocks.push(OpenBlock {
+                    opening: expression,
+                    name,
+                    instructions: vec![],
+                });
+            } else if let Some(name) = expression.strip_prefix('/') {
+                let name = identifier(name.trim(), expression)?;
+                if blocks.len() == 1 {
+                    return Err(format_err!(
+                        "'{{{{/{}}}}}' without a '{{{{#{}}}}}'",
+                        name,
+                        name
+                    ));
+                }
+                let block = blocks.pop().expect("there is more than one block");
+                if block.name != name {
+                    return Err(format_err!(
+                        "'{{{{/{}}}}}' does not close '{{{{{}}}}}'",
+                        name,
+                        block.opening
+                    ));
+                }
+                let instruction = if block.opening == "#first" {
+                    Instruction::First(block.instructions)
+                } else {
+                    Instruction::Section {
+                        component: Component::from_str(name).ok(),
+                        inverted: block.opening.starts_with('^'),
+                        block: block.instructions,
+                    }
+                };
+                blocks
+                    .last_mut()
+                    .expect("there is always a block")
+                    .instructions
+                    .push(instruction);
+            } else {
+                current.extend(value(expression, true)?);
+            }
+        }
+        if let Some(block) = blocks.get(1) {
+            return Err(format_err!(
+                "'{{{{{}}}}}' without a '{{{{/{}}}}}'",
+                block.opening,
+                block.name
+            ));
+        }
+        let mut instructions = blocks.pop().expect("there is always a block").instructions;
+        if !rest.is_empty() {
+            instructions.push(Instruction::Text(rest.to_owned()));
+        }
+        Ok(CompiledTemplate { instructions })
+    }
+
+    /// render the place at the end of `out`
+    pub fn render(&self, place: &BorrowedPlace, out: &mut String) {
+        render(&self.instructions, place, out);
+    }
+}
+
+/// check that the name of a variable (or of a section) is an identifier
+fn identifier<'t>(name: &'t str, expression: &str) -> Result<&'t str, Error> {
+    let is_identifier = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_identifier {
+        Ok(name)
+    } else {
+        Err(format_err!("unsupported expression '{}'", expression))
+    }
+}
+
+/// the instruction of a variable, `None` for an unknown variable
+fn value(name: &str, escape: bool) -> Result<Option<Instruction>, Error> {
+    let name = identifier(name, name)?;
+    Ok(Component::from_str(name)
+        .ok()
+        .map(|component| Instruction::Value { component, escape }))
+}
+
+fn render(instructions: &[Instruction], place: &BorrowedPlace, out: &mut String) {
+    for instruction in instructions {
+        match instruction {
+            Instruction::Text(text) => out.push_str(text),
+            Instruction::Value { component, escape } => {
+                if let Some(value) = &place[*component] {
+                    if *escape {
+                        escape_html(value, out);
+                    } else {
+                        out.push_str(value);
+                    }
+                }
+            }
+            Instruction::First(block) => {
+                // the block is rendered in `out`, then only its first non empty value is kept
+                let start = out.len();
+                render(block, place, out);
+                let rendered = &out[start..];
+                let (begin, end) = rendered
+                    .split("||")
+                    .map(|s| s.trim())
+                    .find(|v| !v.is_empty())
+                    .map(|v| {
+                        let begin = v.as_ptr() as usize - rendered.as_ptr() as usize;
+                        (begin, begin + v.len())
+                    })
+                    .unwrap_or((0, 0));
+                out.truncate(start + end);
+                out.drain(start..start + begin);
+            }
+            Instruction::Section {
+                component,
+                inverted,
+                block,
+            } => {
+                // like in handlebars, any non empty value (even a blank one) is true
+                let has_value = component
+                    .and_then(|c| place[c].as_ref())
+                    .is_some_and(|v| !v.is_empty());
+                if has_value != *inverted {
+                    render(block, place, out);
+                }
+            }
+        }
+    }
+}
+
+/// the html escaping of the `{{value}}` expressions (and of the h-adr markup)
+pub(crate) fn escape_html(value: &str, out: &mut String) {
+    for c in value.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '&' => out.push_str("&amp;"),
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{Component, Configuration, CountryCode, Formatter, Place};
+use failure::{format_err, Error};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// The `ADR` property of a [vCard](https://tools.ietf.org/html/rfc6350#section-6.3.1),
+/// made by [`Formatter::vcard_address`](struct.Formatter.html#method.vcard_address)
+/// or parsed from a vCard.
+///
+/// ```
+/// # #[macro_use] extern crate maplit;
+/// # fn main() {
+///    use address_formatter::Component::*;
+///
+///    let adr = address_formatter::FORMATTER
+///        .vcard_address(
+///            hashmap!(
+///                City => "Berlin",
+///                Country => "Deutschland",
+///                CountryCode => "DE",
+///                HouseNumber => "77",
+///                Postcode => "10117",
+///                Road => "Unter den Linden",
+///            ),
+///            Default::default(),
+///        )
+///        .unwrap();
+///
+///    // the house number is after the road in Germany
+///    assert_eq!(adr.street, "Unter den Linden 77");
+///    assert_eq!(
+///        adr.to_property(),
+///        r#"ADR;LABEL="Unter den Linden 77\n10117 Berlin\nDeutschland":;;Unter den Linden 77;Berlin;;10117;Deutschland"#
+///    );
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VCardAddress {
+    /// the post office box
+    pub po_box: String,
+    /// the extended address (like a building or an apartment)
+    pub extended: String,
+    /// the street address, with the house number
+    pub street: String,
+    /// the locality (like a city)
+    pub locality: String,
+    /// the region (like a state or a province)
+    pub region: String,
+    /// the postal code
+    pub postal_code: String,
+    /// the country name
+    pub country: String,
+    /// the formatted address, given as the `LABEL` parameter
+    pub label: Option<String>,
+}
+
+impl Formatter {
+    /// make the vCard `ADR` property of a [`Place`](struct.Place.html), the `LABEL` being the formatted place.
+    ///
+    /// The house number and the road are written in the order of the country's template
+    /// (like "Unter den Linden 77" in Germany)
+    pub fn vcard_address(
+        &self,
+        into_addr: impl Into<Place>,
+        conf: Configuration,
+    ) -> Result<VCardAddress, Error> {
+        let addr = into_addr.into();
+        let first = |components: &[Component]| {
+            components
+                .iter()
+                .find_map(|c| addr[*c].clone())
+                .unwrap_or_default()
+        };
+
+        let annotated = self.format_annotated_with_config(addr.clone(), conf)?;
+
+        Ok(VCardAddress {
+            po_box: String::new(),
+            extended: first(&[Component::House]),
+            street: annotated.street(),
+            locality: first(&[Component::City, Component::Town, Component::Village]),
+            region: first(&[Component::State]),
+            postal_code: first(&[Component::Postcode]),
+            country: first(&[Component::Country]),
+            label: Some(annotated.text.trim_end().to_owned()),
+        })
+    }
+
+    /// convert a vCard `ADR` property to a [`Place`](struct.Place.html)
+    ///
+    /// The house number is separated from the road on the side where the country's template writes it
+    /// (like "Unter den Linden 77" in Germany, but "17 Rue des Remparts" in France).
+    /// The country is the `Configuration`'s country code, or the ADR's country if it is a country code.
+    pub fn place_from_vcard_address(
+        &self,
+        adr: &VCardAddress,
+        conf: Configuration,
+    ) -> Result<Place, Error> {
+        let mut place = Place::default();
+        let non_empty = |s: &str| Some(s.to_owned()).filter(|s| !s.is_empty());
+        place[Component::House] = non_empty(&adr.extended);
+        place[Component::City] = non_empty(&adr.locality);
+        place[Component::State] = non_empty(&adr.region);
+        place[Component::Postcode] = non_empty(&adr.postal_code);
+        place[Component::Country] = non_empty(&adr.country);
+        place[Component::CountryCode] = conf
+            .country_code
+            .clone()
+            .or_else(|| non_empty(&adr.country).filter(|c| CountryCode::from_str(c).is_ok()));
+
+        // the template tells on which side of the road the house number is
+        let mut probe = Place::default();
+        probe[Component::HouseNumber] = Some("1".to_owned());
+        probe[Component::Road] = Some("Road".to_owned());
+        probe[Component::CountryCode] = place[Component::CountryCode].clone();
+        let probe = self.format_annotated_with_config(probe, conf)?;
+        let start = |component| {
+            probe
+                .spans
+                .iter()
+                .find(|(c, _)| *c == component)
+                .map(|(_, r)| r.start)
+        };
+        let number_after_road = start(Component::Road) < start(Component::HouseNumber);
+
+        let street = adr.street.trim();
+        let (house_number, road) = if number_after_road {
+            match street.rsplit_once(' ') {
+                Some((road, number)) if is_house_number(number) => (Some(number), road),
+                _ => (None, street),
+            }
+        } else {
+            match street.split_once(' ') {
+                Some((number, road)) if is_house_number(number) => (Some(number), road),
+                _ => (None, street),
+            }
+        };
+        place[Component::HouseNumber] = house_number.map(|n| n.trim_end_matches(',').to_owned());
+        place[Component::Road] = non_empty(road.trim_end_matches(','));
+        Ok(place)
+    }
+}
+
+impl VCardAddress {
+    fn fields(&self) -> [&str; 7] {
+        [
+            &self.po_box,
+            &self.extended,
+            &self.street,
+            &self.locality,
+            &self.region,
+            &self.postal_code,
+            &self.country,
+        ]
+    }
+
+    /// the `ADR` property, as written in a vCard (without the line folding)
+    pub fn to_property(&self) -> String {
+        let mut property = "ADR".to_owned();
+        if let Some(label) = &self.label {
+            property.push_str(&format!(";LABEL=\"{}\"", escape_param(label)));
+        }
+        property.push(':');
+        property.push_str(
+            &self
+                .fields()
+                .iter()
+                .map(|f| escape_value(f))
+                .collect::<Vec<_>>()
+                .join(";"),
+        );
+        property
+    }
+
+    /// the `adr` property of a [jCard](https://tools.ietf.org/html/rfc7095)
+    pub fn to_jcard(&self) -> Value {
+        let params = match &self.label {
+            Some(label) => json!({ "label": label }),
+            None => json!({}),
+        };
+        json!(["adr", params, "text", self.fields()])
+    }
+
+    /// parse an `ADR` property of a vCard (like `ADR;TYPE=work:;;17 Rue des Remparts;Papeete;;98714;France`)
+    pub fn parse(property: &str) -> Result<Self, Error> {
+        let (name_and_params, value) = split_unquoted(property, ':')
+            .ok_or_else(|| format_err!("no value in the vCard property '{}'", property))?;
+        let mut params = split_all_unquoted(name_and_params, ';').into_iter();
+        let name = params.next().unwrap_or_default();
+        if !name.eq_ignore_ascii_case("ADR") && !name.to_uppercase().ends_with(".ADR") {
+            return Err(format_err!("'{}' is not an ADR property", name));
+        }
+        let label = params
+            .filter_map(|p| split_unquoted(p, '='))
+            .find(|(k, _)| k.eq_ignore_ascii_case("LABEL"))
+            .map(|(_, v)| unescape_param(v.trim_matches('"')));
+
+        let mut fields = split_value(value).into_iter();
+        let mut next = || fields.next().unwrap_or_default();
+        Ok(VCardAddress {
+            po_box: next(),
+            extended: next(),
+            street: next(),
+            locality: next(),
+            region: next(),
+            postal_code: next(),
+            country: next(),
+            label,
+        })
+    }
+}
+
+/// a house number is made of digits, with an optional letter (like "77a" or "12-14")
+fn is_house_number(s: &str) -> bool {
+    let s = s.trim_end_matches(',');
+    let digits = s.trim_end_matches(|c: char| c.is_alphabetic());
+    s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars().count() - digits.chars().count() <= 1
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '-' || c == '/')
+}
+
+fn escape_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+/// the parameter values cannot contain double quotes, they are escaped as defined by RFC 6868.
+/// The new lines are written as `\n` (like in the vCard 4 labels), so the backslashes are escaped too
+fn escape_param(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('^', "^^")
+        .replace('"', "^'")
+        .replace('\n', "\\n")
+}
+
+/// unescape the RFC 6868 sequences (and the `\n` and `\\` written by the vCard 4 labels) in a single pass,
+/// so an escaped `^` is not read as the start of another sequence
+fn unescape_param(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        let decoded = match (c, chars.peek()) {
+            ('^', Some('n')) | ('\\', Some('n')) | ('\\', Some('N')) => '\n',
+            ('^', Some('\'')) => '"',
+            ('^', Some('^')) => '^',
+            ('\\', Some('\\')) => '\\',
+            (c, _) => {
+                unescaped.push(c);
+                continue;
+            }
+        };
+        chars.next();
+        unescaped.push(decoded);
+    }
+    unescaped
+}
+
+/// split the value of the property on the unescaped ';' and unescape the fields
+fn split_value(value: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => fields.last_mut().unwrap().push('\n'),
+                Some(c) => fields.last_mut().unwrap().push(c),
+                None => {}
+            },
+            ';' => fields.push(String::new()),
+            // the multiple values of a field are kept together
+            ',' => fields.last_mut().unwrap().push_str(", "),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
+
+/// split on the first separator not in double quotes
+fn split_unquoted(s: &str, separator: char) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == separator && !quoted {
+            return Some((&s[..i], &s[i + 1..]));
+        }
+    }
+    None
+}
+
+fn split_all_unquoted(mut s: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    while let Some((part, rest)) = split_unquoted(s, separator) {
+        parts.push(part);
+        s = rest;
+    }
+    parts.push(s);
+    parts
+}
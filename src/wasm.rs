@@ -0,0 +1,229 @@
+//! JavaScript bindings of the formatter, built with wasm-bindgen.
+//!
+//! ```js
+//! import { Formatter, PlaceBuilder, Configuration } from "address-formatter";
+//!
+//! const formatter = new Formatter();
+//! const place = new PlaceBuilder().buildPlace({
+//!     street: "Rue du Médecin-Colonel Calbairac",
+//!     city: "Toulouse",
+//!     country_code: "FR",
+//! });
+//! const conf = new Configuration();
+//! conf.abbreviate = true;
+//! formatter.format(place, conf); // "r du Médecin-Colonel Calbairac\nToulouse\n"
+//! formatter.formatShort(place, new Configuration(), AdminDepth.Locality); // "Rue du Médecin-Colonel Calbairac, Toulouse"
+//! ```
+use crate::Component;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(e: impl std::fmt::Display) -> JsValue {
+    js_sys::Error::new(&e.to_string()).into()
+}
+
+fn read_component(component: &str) -> Result<Component, JsValue> {
+    Component::from_str(component)
+        .map_err(|_| to_js_error(format!("unknown component '{}'", component)))
+}
+
+/// A [`Place`](../struct.Place.html), built by a [`PlaceBuilder`](struct.PlaceBuilder.html)
+#[wasm_bindgen]
+pub struct Place {
+    place: crate::Place,
+}
+
+#[wasm_bindgen]
+impl Place {
+    /// the value of a component (like `road` or `city`), `undefined` if it is not set
+    pub fn get(&self, component: &str) -> Result<Option<String>, JsValue> {
+        let component = read_component(component)?;
+        Ok(self.place[component].clone())
+    }
+
+    /// set the value of a component (like `road` or `city`)
+    pub fn set(&mut self, component: &str, value: Option<String>) -> Result<(), JsValue> {
+        let component = read_component(component)?;
+        self.place[component] = value;
+        Ok(())
+    }
+}
+
+/// The [`AdminDepth`](../enum.AdminDepth.html) of the short labels
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub enum AdminDepth {
+    /// only the road
+    Street,
+    /// the road and the locality (city, town or village)
+    Locality,
+    /// the road, the locality and the county and state
+    State,
+    /// the road, the locality, the county and state and the country
+    Country,
+}
+
+impl From<AdminDepth> for crate::AdminDepth {
+    fn from(depth: AdminDepth) -> Self {
+        match depth {
+            AdminDepth::Street => crate::AdminDepth::Street,
+            AdminDepth::Locality => crate::AdminDepth::Locality,
+            AdminDepth::State => crate::AdminDepth::State,
+            AdminDepth::Country => crate::AdminDepth::Country,
+        }
+    }
+}
+
+/// The [`Configuration`](../struct.Configuration.html) of the formatting
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Configuration {
+    conf: crate::Configuration,
+}
+
+#[wasm_bindgen]
+impl Configuration {
+    /// the default configuration
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// force the use of a given country
+    #[wasm_bindgen(getter = countryCode)]
+    pub fn country_code(&self) -> Option<String> {
+        self.conf.country_code.clone()
+    }
+
+    /// force the use of a given country
+    #[wasm_bindgen(setter = countryCode)]
+    pub fn set_country_code(&mut self, country_code: Option<String>) {
+        self.conf.country_code = country_code;
+    }
+
+    /// use the abbreviations
+    #[wasm_bindgen(getter)]
+    pub fn abbreviate(&self) -> Option<bool> {
+        self.conf.abbreviate
+    }
+
+    /// use the abbreviations
+    #[wasm_bindgen(setter)]
+    pub fn set_abbreviate(&mut self, abbreviate: Option<bool>) {
+        self.conf.abbreviate = abbreviate;
+    }
+
+    /// force the use of a given language for the abbreviations
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> Option<String> {
+        self.conf.language.clone()
+    }
+
+    /// force the use of a given language for the abbreviations
+    #[wasm_bindgen(setter)]
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.conf.language = language;
+    }
+
+    /// country code of the sender, the country is not written for domestic mail
+    #[wasm_bindgen(getter = originCountry)]
+    pub fn origin_country(&self) -> Option<String> {
+        self.conf.origin_country.clone()
+    }
+
+    /// country code of the sender, the country is not written for domestic mail
+    #[wasm_bindgen(setter = originCountry)]
+    pub fn set_origin_country(&mut self, origin_country: Option<String>) {
+        self.conf.origin_country = origin_country;
+    }
+}
+
+/// The [`PlaceBuilder`](../struct.PlaceBuilder.html), with the embedded opencage configuration
+#[wasm_bindgen]
+pub struct PlaceBuilder {
+    builder: crate::PlaceBuilder,
+}
+
+#[wasm_bindgen]
+impl PlaceBuilder {
+    /// build the place builder, throws if the configuration is invalid
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<PlaceBuilder, JsValue> {
+        Ok(PlaceBuilder {
+            builder: crate::PlaceBuilder::try_new().map_err(to_js_error)?,
+        })
+    }
+
+    /// build a place from an object, the keys being the component names or their aliases.
+    ///
+    /// The string and number values are used, the other ones are ignored
+    #[wasm_bindgen(js_name = buildPlace)]
+    pub fn build_place(&self, values: &js_sys::Object) -> Place {
+        let values: Vec<(String, String)> = js_sys::Object::entries(values)
+            .iter()
+            .filter_map(|entry| {
+                let entry = js_sys::Array::from(&entry);
+                let key = entry.get(0).as_string()?;
+                let value = entry.get(1);
+                let value = value
+                    .as_string()
+                    .or_else(|| value.as_f64().map(|v| v.to_string()))?;
+                Some((key, value))
+            })
+            .collect();
+        Place {
+            place: self
+                .builder
+                .build_place(values.iter().map(|(k, v)| (k.as_str(), v.clone()))),
+        }
+    }
+}
+
+/// The [`Formatter`](../struct.Formatter.html), with the embedded opencage configuration
+#[wasm_bindgen]
+pub struct Formatter {
+    formatter: crate::Formatter,
+}
+
+#[wasm_bindgen]
+impl Formatter {
+    /// build the formatter, throws if the configuration is invalid
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<Formatter, JsValue> {
+        Ok(Formatter {
+            formatter: crate::Formatter::try_new().map_err(to_js_error)?,
+        })
+    }
+
+    /// format a place
+    pub fn format(&self, place: &Place, conf: &Configuration) -> Result<String, JsValue> {
+        self.formatter
+            .format_with_config(place.place.clone(), conf.conf.clone())
+            .map_err(to_js_error)
+    }
+
+    /// format a place on only one line
+    #[wasm_bindgen(js_name = formatSingleLine)]
+    pub fn format_single_line(
+        &self,
+        place: &Place,
+        conf: &Configuration,
+    ) -> Result<String, JsValue> {
+        self.formatter
+            .format_single_line(place.place.clone(), conf.conf.clone())
+            .map_err(to_js_error)
+    }
+
+    /// make a short one line label of a place, with only the road and the administrative components down to `depth`
+    #[wasm_bindgen(js_name = formatShort)]
+    pub fn format_short(
+        &self,
+        place: &Place,
+        conf: &Configuration,
+        depth: AdminDepth,
+    ) -> Result<String, JsValue> {
+        self.formatter
+            .format_short(place.place.clone(), conf.conf.clone(), depth.into())
+            .map_err(to_js_error)
+    }
+}
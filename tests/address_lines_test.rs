@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{AddressLines, Component, Formatter};
+use Component::*;
+
+#[test]
+fn address_lines_follow_the_country_template() {
+    let formatter = Formatter::default();
+    let de = formatter
+        .address_lines(
+            hashmap!(
+                City => "Berlin",
+                Country => "Deutschland",
+                CountryCode => "de",
+                House => "Haus der Schweiz",
+                HouseNumber => "24",
+                Postcode => "10117",
+                Road => "Unter den Linden",
+                State => "Berlin",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        de,
+        AddressLines {
+            line1: "Unter den Linden 24".to_owned(),
+            line2: Some("Haus der Schweiz".to_owned()),
+            city: Some("Berlin".to_owned()),
+            state: None,
+            postal_code: Some("10117".to_owned()),
+            country: Some("DE".to_owned()),
+        }
+    );
+
+    let fr = formatter
+        .address_lines(
+            hashmap!(
+                Attention => "Mairie de Toulouse",
+                City => "Toulouse",
+                Country => "France",
+                Postcode => "31000",
+                Road => "Place du Capitole",
+                State => "Occitanie",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(fr.line1, "Place du Capitole");
+    assert_eq!(fr.line2.as_deref(), Some("Mairie de Toulouse"));
+    assert_eq!(fr.city.as_deref(), Some("Toulouse"));
+    // without a country code, the country name is used
+    assert_eq!(fr.country.as_deref(), Some("France"));
+}
+
+#[test]
+fn address_lines_country_code() {
+    let formatter = Formatter::default();
+    let country = |country_code: &str, country: &str| {
+        formatter
+            .address_lines(
+                hashmap!(
+                    City => "City",
+                    Country => country,
+                    CountryCode => country_code,
+                    Road => "Road",
+                ),
+                Default::default(),
+            )
+            .unwrap()
+            .country
+    };
+    assert_eq!(country("uk", "United Kingdom"), Some("GB".to_owned()));
+    // an invalid code is not used
+    assert_eq!(
+        country("usa", "United States of America"),
+        Some("United States of America".to_owned())
+    );
+    // Guadeloupe uses the french rules
+    assert_eq!(country("gp", "Guadeloupe"), Some("FR".to_owned()));
+}
@@ -0,0 +1,113 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{Component::*, Configuration, Formatter, Place};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// an allocator counting the allocations of the current thread
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(|a| a.get())
+}
+
+#[test]
+fn format_into_does_not_allocate() {
+    let formatter = Formatter::default();
+    let places: Vec<(Place, Configuration)> = vec![
+        (
+            hashmap!(
+                City => "Toulouse",
+                Country => "France",
+                CountryCode => "FR",
+                HouseNumber => "17",
+                Postcode => "31000",
+                Road => "Rue du Médecin-Colonel Calbairac",
+                State => "Occitanie",
+            )
+            .into(),
+            Configuration::default(),
+        ),
+        // the state code is found in the state codes
+        (
+            hashmap!(
+                City => "San Francisco",
+                Country => "United States of America",
+                CountryCode => "us",
+                HouseNumber => "301",
+                Postcode => "94103",
+                Road => "Hacker Way",
+                State => "California",
+            )
+            .into(),
+            Configuration::default(),
+        ),
+        // a territory, using the rules of another country
+        (
+            hashmap!(
+                City => "Philipsburg",
+                Country => "Nederland",
+                CountryCode => "NL",
+                Road => "Front Street",
+                State => "Sint Maarten",
+            )
+            .into(),
+            Configuration::default(),
+        ),
+        // a fallback template, without abbreviations to apply
+        (
+            hashmap!(
+                City => "Berlin",
+                Country => "Deutschland",
+                CountryCode => "DE",
+                Suburb => "Mitte",
+            )
+            .into(),
+            Configuration {
+                abbreviate: Some(true),
+                origin_country: Some("DE".to_owned()),
+                ..Default::default()
+            },
+        ),
+    ];
+
+    let mut text = String::new();
+    // the first calls fill the buffers (and the lazily built regexes)
+    for (place, conf) in &places {
+        formatter.format_into(place, conf, &mut text).unwrap();
+    }
+    let expected = text.clone();
+
+    text.clear();
+    let before = allocations();
+    for (place, conf) in &places {
+        formatter.format_into(place, conf, &mut text).unwrap();
+    }
+    let after = allocations();
+
+    assert_eq!(text, expected);
+    assert_eq!(after - before, 0);
+}
@@ -0,0 +1,46 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "address_formatter.h"
+
+#define CHECK(cond)                                                        \
+    do {                                                                   \
+        if (!(cond)) {                                                     \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            exit(1);                                                       \
+        }                                                                  \
+    } while (0)
+
+int main(void) {
+    AddressFormatter *formatter = address_formatter_new();
+    CHECK(formatter != NULL);
+
+    const char *keys[] = {"street", "house_number", "city", "postcode", "country", "country_code"};
+    const char *values[] = {"Avenue de la République", "17", "Paris", "75011", "France", "FR"};
+    AddressFormatterPlace *place = address_formatter_place_new(formatter, keys, values, 6);
+    CHECK(place != NULL);
+
+    char *formatted = address_formatter_format(formatter, place, NULL);
+    CHECK(formatted != NULL);
+    CHECK(strcmp(formatted, "17 Avenue de la République\n75011 Paris\nFrance\n") == 0);
+    address_formatter_string_free(formatted);
+
+    AddressFormatterOptions options = {NULL, 1, NULL, "FR"};
+    formatted = address_formatter_format(formatter, place, &options);
+    CHECK(formatted != NULL);
+    CHECK(strcmp(formatted, "17 Av de la République\n75011 Paris\n") == 0);
+    address_formatter_string_free(formatted);
+
+    /* the errors are reported */
+    const char *invalid_keys[] = {NULL};
+    CHECK(address_formatter_place_new(formatter, invalid_keys, values, 1) == NULL);
+    CHECK(strcmp(address_formatter_last_error(), "key 0 is null") == 0);
+    CHECK(address_formatter_from_config_dir("/not/a/directory") == NULL);
+    CHECK(address_formatter_last_error() != NULL);
+
+    address_formatter_place_free(place);
+    address_formatter_free(formatter);
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,132 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_address-formatter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+const JSON_INPUT: &str = r#"{"house_number": 17, "street": "Rue du Médecin-Colonel Calbairac", "city": "Toulouse", "postcode": "31000", "country": "France", "country_code": "FR"}
+
+{"road": "Avenue de la République", "city": "Paris", "postcode": "75011"}
+"#;
+
+#[test]
+fn json_lines_to_text() {
+    let output = run(&["--country", "FR"], JSON_INPUT);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        r#"17 Rue du Médecin-Colonel Calbairac
+31000 Toulouse
+France
+
+Avenue de la République
+75011 Paris
+"#
+    );
+}
+
+#[test]
+fn json_lines_to_single_line_json_lines() {
+    let output = run(
+        &[
+            "--country",
+            "FR",
+            "--single-line",
+            "--abbreviate",
+            "-o",
+            "jsonl",
+        ],
+        JSON_INPUT,
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        r#"{"formatted":"17 r du Médecin-Colonel Calbairac, 31000 Toulouse, France","id":"<stdin>:1"}
+{"formatted":"Av de la République, 75011 Paris","id":"<stdin>:3"}
+"#
+    );
+}
+
+#[test]
+fn csv_to_csv() {
+    let input = r#"road,house_number,city,state,postcode,country_code
+Hicks Street,301,Brooklyn,New York,11201,US
+"Rue du Médecin-Colonel Calbairac",17,Toulouse,,31000,FR
+"#;
+    let output = run(&["-i", "csv", "-o", "csv", "--single-line"], input);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        r#"id,formatted,error
+<stdin>:2,"301 Hicks Street, Brooklyn, NY 11201",
+<stdin>:3,"17 Rue du Médecin-Colonel Calbairac, 31000 Toulouse",
+"#
+    );
+}
+
+#[test]
+fn invalid_records_are_reported() {
+    let input = "not json\n{\"road\": \"Hicks Street\", \"country_code\": \"US\"}\n";
+    let output = run(&["--single-line"], input);
+    assert_eq!(output.status.code(), Some(1));
+    // the invalid record is written as an empty line, to keep the lines aligned with the input
+    assert_eq!(stdout(&output), "\nHicks Street\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("<stdin>:1: invalid JSON object"));
+}
+
+#[test]
+fn invalid_records_have_an_error_row() {
+    let input = r#"{"id": "a", "road": "Hicks Street", "country_code": "US"}
+not json
+{"id": "c", "road": "Rue Lepic", "country_code": "FR"}
+"#;
+    let output = run(&["--id-field", "id", "-o", "jsonl"], input);
+    assert_eq!(output.status.code(), Some(1));
+    let lines: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    // the id field is not added to the address
+    assert_eq!(
+        lines[0],
+        serde_json::json!({"id": "a", "formatted": "Hicks Street"})
+    );
+    assert_eq!(lines[1]["id"], "");
+    assert_eq!(lines[1]["formatted"], serde_json::Value::Null);
+    assert!(lines[1]["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("<stdin>:2: invalid JSON object"));
+    assert_eq!(lines[2]["id"], "c");
+
+    let input = "id,road,country_code\n1,Hicks Street,US\n2\n3,Unter den Linden,DE\n";
+    let output = run(&["-i", "csv", "-o", "csv", "--id-field", "id"], input);
+    assert_eq!(output.status.code(), Some(1));
+    let out = stdout(&output);
+    let rows: Vec<&str> = out.lines().collect();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0], "id,formatted,error");
+    assert_eq!(rows[1], "1,Hicks Street,");
+    assert!(rows[2].starts_with(",,\"<stdin>:3: invalid CSV record"));
+    assert_eq!(rows[3], "3,Unter den Linden,");
+}
@@ -0,0 +1,162 @@
+use address_formatter::{ConfigurationIssue, Formatter, PlaceBuilder};
+use std::path::Path;
+use tempfile::TempDir;
+
+/// copy the opencage configuration in a temporary directory (removed when dropped), so it can be altered
+fn copy_config(name: &str) -> TempDir {
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let path = entry.unwrap().path();
+            let dest = to.join(path.file_name().unwrap());
+            if path.is_dir() {
+                copy_dir(&path, &dest);
+            } else {
+                std::fs::copy(&path, &dest).unwrap();
+            }
+        }
+    }
+    let dir = tempfile::Builder::new()
+        .prefix(&format!("address_formatter_{}", name))
+        .tempdir()
+        .unwrap();
+    copy_dir(Path::new("./address-formatting/conf"), dir.path());
+    dir
+}
+
+fn append(file: &Path, content: &str) {
+    let mut data = std::fs::read_to_string(file).unwrap();
+    data.push_str(content);
+    std::fs::write(file, data).unwrap();
+}
+
+fn issue(
+    file: &str,
+    country: Option<&str>,
+    rule: Option<&str>,
+) -> (String, Option<String>, Option<String>) {
+    (
+        file.to_owned(),
+        country.map(|c| c.to_owned()),
+        rule.map(|r| r.to_owned()),
+    )
+}
+
+fn locations(issues: &[ConfigurationIssue]) -> Vec<(String, Option<String>, Option<String>)> {
+    issues
+        .iter()
+        .map(|i| (i.file.clone(), i.country.clone(), i.rule.clone()))
+        .collect()
+}
+
+#[test]
+fn valid_configuration() {
+    assert!(Formatter::try_new().is_ok());
+    assert!(PlaceBuilder::try_new().is_ok());
+}
+
+#[test]
+fn all_the_errors_are_reported() {
+    let tmp = copy_config("all_the_errors_are_reported");
+    let dir = tmp.path();
+    append(
+        &dir.join("countries/worldwide.yaml"),
+        r#"
+XA:
+    replace:
+        - ["[unclosed", ""]
+        - ["not_a_component=Foo", "Bar"]
+XB:
+    use_country: XC
+XD:
+    use_country: FR
+    add_component: state
+XE:
+    address_template: *generic1
+    postformat_replace:
+        - ["city=Foo", "Bar"]
+"#,
+    );
+    append(
+        &dir.join("state_codes.yaml"),
+        "\nNOT_A_COUNTRY:\n    AA: Foo\n",
+    );
+
+    let error = Formatter::try_from_config_dir(dir).err().unwrap();
+    let mut found = locations(&error.issues);
+    found.sort();
+    assert_eq!(
+        found,
+        vec![
+            issue(
+                "countries/worldwide.yaml",
+                Some("XA"),
+                Some("address_template")
+            ),
+            issue(
+                "countries/worldwide.yaml",
+                Some("XA"),
+                Some("replace: [unclosed")
+            ),
+            issue(
+                "countries/worldwide.yaml",
+                Some("XA"),
+                Some("replace: not_a_component=Foo")
+            ),
+            issue("countries/worldwide.yaml", Some("XB"), Some("use_country")),
+            issue(
+                "countries/worldwide.yaml",
+                Some("XD"),
+                Some("add_component: state")
+            ),
+            issue(
+                "countries/worldwide.yaml",
+                Some("XE"),
+                Some("postformat_replace: city=Foo")
+            ),
+            issue("state_codes.yaml", Some("NOT_A_COUNTRY"), None),
+        ]
+    );
+    // the error message lists all the problems
+    let message = error.to_string();
+    assert!(message.starts_with("invalid configuration, 7 problem(s) found"));
+    assert!(message.contains("countries/worldwide.yaml, country XB, rule 'use_country': "));
+
+    // the non fallible constructor also returns an error
+    assert!(Formatter::from_config_dir(dir).is_err());
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let tmp = copy_config("missing_files_are_reported");
+    let dir = tmp.path();
+    std::fs::remove_file(dir.join("state_codes.yaml")).unwrap();
+    std::fs::remove_file(dir.join("components.yaml")).unwrap();
+
+    let error = Formatter::try_from_config_dir(dir).err().unwrap();
+    assert_eq!(
+        locations(&error.issues),
+        vec![issue("state_codes.yaml", None, None)]
+    );
+    let error = PlaceBuilder::try_from_config_dir(dir).err().unwrap();
+    assert_eq!(
+        locations(&error.issues),
+        vec![issue("components.yaml", None, None)]
+    );
+}
+
+#[test]
+fn invalid_components() {
+    let tmp = copy_config("invalid_components");
+    let dir = tmp.path();
+    append(
+        &dir.join("components.yaml"),
+        "\n---\nname: not_a_component\naliases:\n    - foo\n",
+    );
+
+    let error = PlaceBuilder::try_from_config_dir(dir).err().unwrap();
+    assert_eq!(
+        locations(&error.issues),
+        vec![issue("components.yaml", None, Some("not_a_component"))]
+    );
+}
@@ -0,0 +1,87 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// the header must be the one generated by cbindgen from `src/ffi.rs`,
+/// it is updated when the test is run with `ADDRESS_FORMATTER_UPDATE_HEADER=1`
+#[test]
+fn header_is_up_to_date() {
+    let config = cbindgen::Config::from_file(root().join("cbindgen.toml")).unwrap();
+    let bindings = cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(root().join("src/ffi.rs"))
+        .generate()
+        .unwrap();
+    let mut generated = vec![];
+    bindings.write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    let header_path = root().join("include/address_formatter.h");
+    if std::env::var_os("ADDRESS_FORMATTER_UPDATE_HEADER").is_some() {
+        std::fs::write(&header_path, &generated).unwrap();
+    }
+    let header = std::fs::read_to_string(&header_path).unwrap();
+    assert!(
+        header == generated,
+        "include/address_formatter.h is out of date, \
+         run the test with ADDRESS_FORMATTER_UPDATE_HEADER=1 to update it"
+    );
+}
+
+/// build the cdylib, as documented in the README
+fn build_cdylib(target_dir: &Path) -> PathBuf {
+    let status = Command::new(env!("CARGO"))
+        .current_dir(root())
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "cdylib",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--target-dir")
+        .arg(target_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    target_dir.join("debug")
+}
+
+/// compile the C test program against the cdylib and run it
+#[test]
+#[cfg(unix)]
+fn c_program() {
+    let lib_dir = build_cdylib(&Path::new(env!("CARGO_TARGET_TMPDIR")).join("ffi"));
+    let tmp = tempfile::tempdir().unwrap();
+    let program = tmp.path().join("ffi_test");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_owned());
+    let status = Command::new(compiler)
+        .arg(root().join("tests/c/ffi_test.c"))
+        .arg("-I")
+        .arg(root().join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-laddress_formatter")
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(&program)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ok\n");
+}
@@ -0,0 +1,148 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{Component, FormatterBuilder};
+
+#[test]
+fn custom_template() {
+    use Component::*;
+    let formatter = FormatterBuilder::default()
+        .address_template(
+            "BR",
+            r#"{{{road}}}, {{{house_number}}}
+{{{neighbourhood}}}
+{{#first}} {{{city}}} || {{{town}}} {{/first}} - {{{state_code}}}
+{{{postcode}}}
+{{{country}}}"#,
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        formatter
+            .format(hashmap!(
+                City => "São Paulo",
+                Country => "Brasil",
+                CountryCode => "BR",
+                HouseNumber => "1578",
+                Neighbourhood => "Bela Vista",
+                Postcode => "01310-200",
+                Road => "Avenida Paulista",
+                State => "São Paulo",
+            ))
+            .unwrap(),
+        r#"Avenida Paulista, 1578
+Bela Vista
+São Paulo - SP
+01310-200
+Brasil
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn custom_rules() {
+    use Component::*;
+    let formatter = FormatterBuilder::default()
+        .replace("PT", "road=^Rua ", "R. ")
+        .postformat_replace("PT", "\nPortugal$", "\nPORTUGAL")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        formatter
+            .format(hashmap!(
+                City => "Lisboa",
+                Country => "Portugal",
+                CountryCode => "PT",
+                HouseNumber => "10",
+                Postcode => "1100-148",
+                Road => "Rua Augusta",
+            ))
+            .unwrap(),
+        r#"R. Augusta 10
+1100-148 Lisboa
+PORTUGAL
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn custom_use_country() {
+    use Component::*;
+    let formatter = FormatterBuilder::default()
+        .use_country("XX", "FR")
+        .change_country("XX", "Neverland")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        formatter
+            .format(hashmap!(
+                City => "Toulouse",
+                CountryCode => "XX",
+                HouseNumber => "17",
+                Postcode => "31000",
+                Road => "Rue du Médecin-Colonel Calbairac",
+            ))
+            .unwrap(),
+        r#"17 Rue du Médecin-Colonel Calbairac
+31000 Toulouse
+Neverland
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn all_the_errors_are_reported() {
+    let error = FormatterBuilder::default()
+        .address_template("FRA", "{{{road}}}")
+        .fallback_template("FR", "{{#first}} {{{road}}}")
+        .replace("FR", "not_a_component=Rue", "R.")
+        .replace("FR", "[unclosed", "")
+        .postformat_replace("FR", "road=Rue", "R.")
+        .use_country("XX", "YY")
+        .build()
+        .err()
+        .unwrap();
+
+    let found: Vec<_> = error
+        .issues
+        .iter()
+        .map(|i| (i.file.as_str(), i.country.as_deref(), i.rule.as_deref()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("FormatterBuilder", Some("FRA"), Some("address_template")),
+            ("FormatterBuilder", Some("FR"), Some("fallback_template")),
+            (
+                "FormatterBuilder",
+                Some("FR"),
+                Some("replace: not_a_component=Rue")
+            ),
+            ("FormatterBuilder", Some("FR"), Some("replace: [unclosed")),
+            (
+                "FormatterBuilder",
+                Some("FR"),
+                Some("postformat_replace: road=Rue")
+            ),
+            ("FormatterBuilder", Some("XX"), Some("use_country")),
+        ]
+    );
+}
+
+#[test]
+fn only_the_opencage_template_subset_is_supported() {
+    let error = FormatterBuilder::default()
+        .address_template("DE", "{{#if road}}{{{road}}}{{/if}}")
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(error.issues.len(), 1);
+    assert!(error.issues[0]
+        .message
+        .contains("unsupported expression '#if road'"));
+}
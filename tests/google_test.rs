@@ -0,0 +1,62 @@
+use address_formatter::{Component, GoogleAddressComponent, Place};
+
+#[test]
+fn google_round_trip() {
+    let mut place = Place::default();
+    place[Component::HouseNumber] = Some("17".to_owned());
+    place[Component::Road] = Some("Rue du Médecin-Colonel Calbairac".to_owned());
+    place[Component::City] = Some("Toulouse".to_owned());
+    place[Component::County] = Some("Haute-Garonne".to_owned());
+    place[Component::CountyCode] = Some("31".to_owned());
+    place[Component::State] = Some("Occitanie".to_owned());
+    place[Component::Postcode] = Some("31000".to_owned());
+    place[Component::Country] = Some("France".to_owned());
+    place[Component::CountryCode] = Some("FR".to_owned());
+
+    let components = place.to_google_address_components();
+    assert_eq!(
+        components
+            .iter()
+            .map(|c| c.types[0].as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "street_number",
+            "route",
+            "locality",
+            "administrative_area_level_2",
+            "administrative_area_level_1",
+            "country",
+            "postal_code"
+        ]
+    );
+    // without a code, the long name is used as short name
+    assert_eq!(components[4].short_name, "Occitanie");
+
+    // the short name is not a code when it is the long name
+    let read = Place::from_google_address_components(&components);
+    assert_eq!(read[Component::StateCode], None);
+    assert_eq!(*read, *place);
+}
+
+#[test]
+fn google_postal_town() {
+    // in the United Kingdom, the city is given as a postal town
+    let components: Vec<GoogleAddressComponent> = serde_json::from_str(
+        r#"[
+            {"long_name": "10", "short_name": "10", "types": ["street_number"]},
+            {"long_name": "Downing Street", "short_name": "Downing St", "types": ["route"]},
+            {"long_name": "London", "short_name": "London", "types": ["postal_town"]},
+            {"long_name": "England", "short_name": "England", "types": ["administrative_area_level_1", "political"]},
+            {"long_name": "United Kingdom", "short_name": "GB", "types": ["country", "political"]},
+            {"long_name": "SW1A 2AA", "short_name": "SW1A 2AA", "types": ["postal_code"]}
+        ]"#,
+    )
+    .unwrap();
+    let place = Place::from_google_address_components(&components);
+    assert_eq!(place[Component::City], Some("London".to_owned()));
+    assert_eq!(place[Component::CountryCode], Some("GB".to_owned()));
+    assert_eq!(
+        address_formatter::FORMATTER.format(place).unwrap(),
+        "10 Downing Street\nLondon SW1A 2AA\nUnited Kingdom\n"
+    );
+}
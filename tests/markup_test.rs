@@ -0,0 +1,107 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{Component, Configuration, Formatter};
+use Component::*;
+
+#[test]
+fn h_adr_us_line_order_and_escaping() {
+    let formatter = Formatter::default();
+    let html = formatter
+        .format_h_adr(
+            hashmap!(
+                Attention => "Jack & Jill's <Diner>",
+                City => "Brooklyn",
+                Country => "United States of America",
+                CountryCode => "US",
+                HouseNumber => "301",
+                Postcode => "11201",
+                Road => "Hicks Street",
+                State => "New York",
+                StateCode => "NY",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        html,
+        concat!(
+            r#"<div class="h-adr">"#,
+            // the attention is not a h-adr property
+            r#"Jack &amp; Jill's &lt;Diner&gt;<br>"#,
+            r#"<span class="p-street-address">301 Hicks Street</span><br>"#,
+            r#"<span class="p-locality">Brooklyn</span>, <span class="p-region">NY</span> "#,
+            r#"<span class="p-postal-code">11201</span><br>"#,
+            r#"<span class="p-country-name">United States of America</span>"#,
+            "</div>"
+        )
+    );
+}
+
+#[test]
+fn h_adr_de_house_number_after_road() {
+    let html = Formatter::default()
+        .format_h_adr(
+            hashmap!(
+                City => "Berlin",
+                Country => "Deutschland",
+                CountryCode => "DE",
+                HouseNumber => "77",
+                Postcode => "10117",
+                Road => "Unter den Linden",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert!(html.contains(r#"<span class="p-street-address">Unter den Linden 77</span><br>"#));
+}
+
+#[test]
+fn json_ld_postal_address() {
+    let formatter = Formatter::default();
+    let json_ld = formatter
+        .postal_address_json_ld(
+            hashmap!(
+                City => "Brooklyn",
+                HouseNumber => "301",
+                Postcode => "11201",
+                Road => "Hicks Street",
+                State => "New York",
+            ),
+            Configuration {
+                country_code: Some("us".to_owned()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        json_ld,
+        serde_json::json!({
+            "@context": "https://schema.org",
+            "@type": "PostalAddress",
+            "streetAddress": "301 Hicks Street",
+            "addressLocality": "Brooklyn",
+            "addressRegion": "New York",
+            "postalCode": "11201",
+            "addressCountry": "US",
+        })
+    );
+}
+
+#[test]
+fn json_ld_street_of_a_territory() {
+    let json_ld = Formatter::default()
+        .postal_address_json_ld(
+            hashmap!(
+                City => "Gustavia",
+                Country => "Saint-Barthélemy",
+                CountryCode => "BL",
+                HouseNumber => "5",
+                Postcode => "97133",
+                Road => "Rue Jeanne d'Arc",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(json_ld["streetAddress"], "5 Rue Jeanne d'Arc");
+    assert_eq!(json_ld["addressCountry"], "BL");
+}
@@ -0,0 +1,203 @@
+use address_formatter::{Component, Formatter, PlaceBuilder};
+use serde_json::json;
+
+fn jsonv2_result() -> serde_json::Value {
+    json!({
+        "place_id": 296963537,
+        "licence": "Data © OpenStreetMap contributors, ODbL 1.0. https://osm.org/copyright",
+        "osm_type": "way",
+        "osm_id": 24381451,
+        "lat": "40.6937349",
+        "lon": "-73.9937016",
+        "category": "amenity",
+        "type": "library",
+        "place_rank": 30,
+        "importance": 0.00000999999999995449,
+        "addresstype": "amenity",
+        "name": "Brooklyn Heights Library",
+        "display_name": "Brooklyn Heights Library, 286, Cadman Plaza West, Brooklyn Heights, Brooklyn, Kings County, New York, 11201, United States",
+        "address": {
+            "amenity": "Brooklyn Heights Library",
+            "house_number": "286",
+            "road": "Cadman Plaza West",
+            "neighbourhood": "Brooklyn Heights",
+            "suburb": "Brooklyn",
+            "county": "Kings County",
+            "city": "New York",
+            "state": "New York",
+            "ISO3166-2-lvl4": "US-NY",
+            "postcode": "11201",
+            "country": "United States",
+            "country_code": "us"
+        },
+        "boundingbox": ["40.6933", "40.6941", "-73.9942", "-73.9932"]
+    })
+}
+
+#[test]
+fn jsonv2() {
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&jsonv2_result())
+        .unwrap();
+    assert_eq!(
+        place[Component::Attention],
+        Some("Brooklyn Heights Library".to_owned())
+    );
+    assert_eq!(place[Component::StateCode], Some("NY".to_owned()));
+    assert_eq!(place[Component::CountryCode], Some("US".to_owned()));
+    assert_eq!(
+        Formatter::default().format(place).unwrap(),
+        r#"Brooklyn Heights Library
+286 Cadman Plaza West
+New York, NY 11201
+United States of America
+"#
+    );
+
+    // without a name (like with the `json` format), the name is found in the address under the feature's type
+    let mut result = jsonv2_result();
+    result.as_object_mut().unwrap().remove("name");
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&result)
+        .unwrap();
+    assert_eq!(
+        place[Component::Attention],
+        Some("Brooklyn Heights Library".to_owned())
+    );
+}
+
+#[test]
+fn jsonv2_administrative_levels() {
+    let result = json!({
+        "name": "Sagrada Família",
+        "address": {
+            "road": "Carrer de Mallorca",
+            "city": "Barcelona",
+            "province": "Barcelona",
+            "ISO3166-2-lvl6": "ES-B",
+            "state": "Catalunya",
+            "ISO3166-2-lvl4": "ES-CT",
+            "country_code": "es"
+        }
+    });
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&result)
+        .unwrap();
+    assert_eq!(place[Component::StateCode], Some("CT".to_owned()));
+    assert_eq!(place[Component::CountyCode], Some("B".to_owned()));
+
+    // a county code is not used as state code when there is no state level
+    let result = json!({
+        "address": {
+            "road": "Rue du Médecin-Colonel Calbairac",
+            "city": "Toulouse",
+            "ISO3166-2-lvl6": "FR-31",
+            "country_code": "fr"
+        }
+    });
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&result)
+        .unwrap();
+    assert_eq!(place[Component::StateCode], None);
+    assert_eq!(place[Component::CountyCode], Some("31".to_owned()));
+}
+
+#[test]
+fn jsonv2_without_address_details() {
+    let mut result = jsonv2_result();
+    result.as_object_mut().unwrap().remove("address");
+    let display_name = result["display_name"].as_str().unwrap().to_owned();
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&result)
+        .unwrap();
+    assert_eq!(place[Component::Road], Some(display_name.clone()));
+    assert_eq!(place[Component::Attention], None);
+    assert_eq!(
+        Formatter::default().format(place).unwrap(),
+        format!("{}\n", display_name)
+    );
+
+    result.as_object_mut().unwrap().remove("display_name");
+    let error = PlaceBuilder::default()
+        .build_place_from_nominatim(&result)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("addressdetails=1"));
+}
+
+#[test]
+fn geocodejson() {
+    let response = json!({
+        "type": "FeatureCollection",
+        "geocoding": {"version": "0.1.0", "attribution": "Data © OpenStreetMap contributors"},
+        "features": [{
+            "type": "Feature",
+            "properties": {
+                "geocoding": {
+                    "place_id": 100149,
+                    "osm_type": "node",
+                    "osm_id": 2138946047,
+                    "type": "house",
+                    "label": "17, Rue du Médecin-Colonel Calbairac, Lafourguette, Toulouse, 31000, France",
+                    "housenumber": "17",
+                    "street": "Rue du Médecin-Colonel Calbairac",
+                    "locality": "Lafourguette",
+                    "district": "Saint-Cyprien",
+                    "postcode": "31000",
+                    "city": "Toulouse",
+                    "county": "Haute-Garonne",
+                    "state": "Occitanie",
+                    "country": "France",
+                    "country_code": "fr",
+                    "admin": {"level2": "France", "level4": "Occitanie", "level6": "Haute-Garonne"}
+                }
+            },
+            "geometry": {"type": "Point", "coordinates": [1.4066, 43.5750]}
+        }]
+    });
+    let places = PlaceBuilder::default()
+        .build_places_from_nominatim(&response)
+        .unwrap();
+    assert_eq!(places.len(), 1);
+    assert_eq!(places[0][Component::HouseNumber], Some("17".to_owned()));
+    assert_eq!(
+        places[0][Component::Village],
+        Some("Lafourguette".to_owned())
+    );
+    assert_eq!(
+        places[0][Component::CityDistrict],
+        Some("Saint-Cyprien".to_owned())
+    );
+    assert_eq!(
+        Formatter::default().format(places[0].clone()).unwrap(),
+        r#"17 Rue du Médecin-Colonel Calbairac
+31000 Toulouse
+France
+"#
+    );
+}
+
+#[test]
+fn geocodejson_locality_without_city() {
+    let feature = json!({
+        "type": "Feature",
+        "properties": {
+            "geocoding": {
+                "type": "house",
+                "housenumber": "3",
+                "street": "Chemin du Moulin",
+                "locality": "Le Bourg",
+                "postcode": "46100",
+                "country": "France",
+                "country_code": "fr"
+            }
+        }
+    });
+    let place = PlaceBuilder::default()
+        .build_place_from_nominatim(&feature)
+        .unwrap();
+    assert_eq!(
+        Formatter::default().format(place).unwrap(),
+        "3 Chemin du Moulin\n46100 Le Bourg\nFrance\n"
+    );
+}
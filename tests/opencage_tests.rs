@@ -16,16 +16,15 @@ pub fn opencage_tests() {
         .filter_map(|f| {
             f.contents_utf8().map(|s| {
                 (
-                    YamlLoader::load_from_str(s).expect(&format!(
-                        "impossible to read test file {}",
-                        f.path().display()
-                    )),
+                    YamlLoader::load_from_str(s).unwrap_or_else(|_| {
+                        panic!("impossible to read test file {}", f.path().display())
+                    }),
                     f.path().to_str().unwrap(),
                 )
             })
         })
-        .flat_map(|(s, file_name)| s.into_iter().map(move |s| (s, file_name.clone())))
-        .map(|(t, file_name)| run_test(t, &file_name, &formatter, &places_builder))
+        .flat_map(|(s, file_name)| s.into_iter().map(move |s| (s, file_name)))
+        .map(|(t, file_name)| run_test(t, file_name, &formatter, &places_builder))
         .filter_map(|r| r.err())
         .map(|e| {
             log::error!("test on error: {}", e);
@@ -69,8 +68,30 @@ fn run_test(
         places_builder,
     )?;
 
+    let annotated = formatter.format_annotated(addr.clone())?;
     let formated_value = formatter.format(addr)?;
 
+    // the annotated text must always be the same as the formatted one
+    if annotated.text != formated_value {
+        return Err(format_err!(
+            "for file {}, test \"{}\", the annotated text '{}' is not '{}'",
+            file_name,
+            description,
+            annotated.text,
+            formated_value
+        ));
+    }
+    for (_, span) in &annotated.spans {
+        if annotated.text.get(span.clone()).is_none() {
+            return Err(format_err!(
+                "for file {}, test \"{}\", invalid span {:?}",
+                file_name,
+                description,
+                span
+            ));
+        }
+    }
+
     if formated_value != expected {
         Err(format_err!(
             r#"
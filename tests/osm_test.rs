@@ -0,0 +1,63 @@
+use address_formatter::{Component, Formatter, PlaceBuilder};
+
+#[test]
+fn addr_tags() {
+    let tags = vec![
+        ("building", "apartments"),
+        ("addr:housenumber", "301"),
+        ("addr:street", "Hicks Street"),
+        ("addr:unit", "4B"),
+        ("addr:city", "Brooklyn"),
+        ("addr:state", "NY"),
+        ("addr:postcode", "11201"),
+        ("addr:country", "us"),
+        ("addr:interpolation", "odd"),
+    ];
+    let place = PlaceBuilder::default().build_place_from_osm_tags(tags);
+    assert_eq!(place[Component::CountryCode], Some("US".to_owned()));
+    assert_eq!(place[Component::Attention], None);
+    assert_eq!(
+        Formatter::default().format(place).unwrap(),
+        "4B\n301 Hicks Street\nBrooklyn, NY 11201\n"
+    );
+}
+
+#[test]
+fn addr_place() {
+    // in some countries the addresses are attached to a place instead of a street
+    let tags = vec![
+        ("addr:housenumber", "12"),
+        ("addr:place", "Lafourguette"),
+        ("addr:city", "Toulouse"),
+        ("addr:postcode", "31100"),
+        ("addr:country", "FR"),
+    ];
+    assert_eq!(
+        Formatter::default()
+            .format(PlaceBuilder::default().build_place_from_osm_tags(tags))
+            .unwrap(),
+        "12 Lafourguette\n31100 Toulouse\n"
+    );
+}
+
+#[test]
+fn addr_full() {
+    let builder = PlaceBuilder::default();
+    let full = "Mairie, Rue des Remparts, 98714 Papeete";
+    let place = builder.build_place_from_osm_tags(vec![("name", "Mairie"), ("addr:full", full)]);
+    assert_eq!(place[Component::Road], Some(full.to_owned()));
+    assert_eq!(place[Component::Attention], Some("Mairie".to_owned()));
+
+    // addr:full is only a fallback
+    let place = builder.build_place_from_osm_tags(vec![
+        ("addr:full", full),
+        ("addr:street", "Rue des Remparts"),
+    ]);
+    assert_eq!(place[Component::Road], Some("Rue des Remparts".to_owned()));
+
+    // the country is not a structured address
+    let place =
+        builder.build_place_from_osm_tags(vec![("addr:full", full), ("addr:country", "pf")]);
+    assert_eq!(place[Component::Road], Some(full.to_owned()));
+    assert_eq!(place[Component::CountryCode], Some("PF".to_owned()));
+}
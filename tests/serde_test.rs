@@ -0,0 +1,45 @@
+use address_formatter::{Component, Place, PlaceBuilder};
+use serde::de::DeserializeSeed;
+
+#[test]
+fn place_round_trip() {
+    let mut place = Place::default();
+    place[Component::Road] = Some("Rue du Médecin-Colonel Calbairac".to_owned());
+    place[Component::CountryCode] = Some("FR".to_owned());
+
+    let json = serde_json::to_string(&place).unwrap();
+    let read: Place = serde_json::from_str(&json).unwrap();
+    assert_eq!(*read, *place);
+}
+
+#[test]
+fn place_with_unknown_component() {
+    let error = serde_json::from_str::<Place>(r#"{"street": "Rue du Médecin-Colonel Calbairac"}"#)
+        .err()
+        .unwrap();
+    assert!(error
+        .to_string()
+        .starts_with("unknown component `street`, expected one of `attention`, "));
+}
+
+#[test]
+fn component() {
+    assert_eq!(
+        serde_json::from_str::<Vec<Component>>(r#"["road", "country_code"]"#).unwrap(),
+        vec![Component::Road, Component::CountryCode]
+    );
+    assert!(serde_json::from_str::<Component>(r#""street""#).is_err());
+}
+
+#[test]
+fn place_with_aliases() {
+    let builder = PlaceBuilder::default();
+    let mut deserializer = serde_json::Deserializer::from_str(
+        r#"{"street": "Rue des Remparts", "house_number": 12, "province": null, "building": "Mairie"}"#,
+    );
+    let place = (&builder).deserialize(&mut deserializer).unwrap();
+    assert_eq!(place[Component::Road], Some("Rue des Remparts".to_owned()));
+    assert_eq!(place[Component::HouseNumber], Some("12".to_owned()));
+    assert_eq!(place[Component::State], None);
+    assert_eq!(place[Component::House], Some("Mairie".to_owned()));
+}
@@ -0,0 +1,120 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+/// the server, killed at the end of the test
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    fn start() -> Self {
+        Self::start_with(&[])
+    }
+
+    fn start_with(args: &[&str]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_address-formatter-server"))
+            .args(["--bind", "127.0.0.1:0"])
+            .args(args)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let address = line.trim().trim_start_matches("listening on ").to_owned();
+        Server { child, address }
+    }
+
+    /// send a request, and give the status code and the body of the response
+    fn request(&self, method: &str, url: &str, body: &str) -> (u16, serde_json::Value) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            url,
+            self.address,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, serde_json::from_str(body).unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+#[test]
+fn server_endpoints() {
+    let server = Server::start();
+
+    assert_eq!(
+        server.request("GET", "/health", ""),
+        (200, serde_json::json!({"status": "ok"}))
+    );
+
+    let address = r#"{"street": "Avenue de la République", "city": "Paris", "postcode": 75011}"#;
+    assert_eq!(
+        server.request("POST", "/format?country_code=FR&abbreviate=true", address),
+        (
+            200,
+            serde_json::json!({"formatted": "Av de la République\n75011 Paris\n"})
+        )
+    );
+    assert_eq!(
+        server.request("POST", "/place", address),
+        (
+            200,
+            serde_json::json!({"road": "Avenue de la République", "city": "Paris", "postcode": "75011"})
+        )
+    );
+
+    let batch = r#"[
+        {"road": "Rue du Médecin-Colonel Calbairac", "city": "Toulouse", "country_code": "FR"},
+        "not an object"
+    ]"#;
+    let (status, response) = server.request("POST", "/format/batch", batch);
+    assert_eq!(status, 200);
+    assert_eq!(
+        response["results"][0],
+        serde_json::json!({"formatted": "Rue du Médecin-Colonel Calbairac\nToulouse\n"})
+    );
+    assert!(response["results"][1]["error"].is_string());
+
+    // the errors
+    assert_eq!(
+        server
+            .request("POST", "/format?abbreviate=maybe", address)
+            .0,
+        400
+    );
+    assert_eq!(server.request("POST", "/format?foo=bar", address).0, 400);
+    assert_eq!(server.request("POST", "/format", "not json").0, 400);
+    assert_eq!(server.request("GET", "/format", "").0, 405);
+    assert_eq!(server.request("GET", "/unknown", "").0, 404);
+}
+
+#[test]
+fn too_large_bodies_are_rejected() {
+    let server = Server::start_with(&["--max-body-size", "100"]);
+    let address = r#"{"road": "Avenue de la République", "city": "Paris", "country_code": "FR"}"#;
+    assert_eq!(server.request("POST", "/format", address).0, 200);
+
+    let (status, body) = server.request("POST", "/format", &address.repeat(2));
+    assert_eq!(status, 413);
+    assert_eq!(
+        body,
+        serde_json::json!({"error": "the body is larger than the limit of 100 bytes"})
+    );
+}
@@ -1,6 +1,6 @@
 #[macro_use]
 extern crate maplit;
-use address_formatter::{Component, Formatter, Place};
+use address_formatter::{Component, Configuration, Formatter, Place, PlaceBuilder};
 
 #[test]
 pub fn basic_test() {
@@ -81,7 +81,7 @@ pub fn address_builder() {
         ("state", "French Polynesia"),
     ];
 
-    let addr = addr_builder.build_place(data.into_iter().map(|(k, v)| (k.clone(), v.to_string())));
+    let addr = addr_builder.build_place(data.iter().map(|(k, v)| (*k, v.to_string())));
 
     assert_eq!(
         formatter.format(addr).unwrap(),
@@ -94,6 +94,29 @@ Polynésie française
     )
 }
 
+#[test]
+fn address_builder_attention() {
+    let addr_builder = PlaceBuilder::default();
+
+    // all the fields are components or aliases, there is nothing to put in the attention
+    let addr = addr_builder.build_place(vec![
+        ("street", "Rue des Remparts".to_owned()),
+        ("city", "Papeete".to_owned()),
+    ]);
+    assert_eq!(addr[Component::Road].as_deref(), Some("Rue des Remparts"));
+    assert_eq!(addr[Component::Attention], None);
+
+    // the unknown fields are put in the attention
+    let addr = addr_builder.build_place(vec![
+        ("street", "Rue des Remparts".to_owned()),
+        ("name", "Mairie de Papeete".to_owned()),
+    ]);
+    assert_eq!(
+        addr[Component::Attention].as_deref(),
+        Some("Mairie de Papeete")
+    );
+}
+
 #[test]
 fn use_of_singleton() {
     assert_eq!(
@@ -112,3 +135,503 @@ France
         .to_owned()
     )
 }
+
+#[test]
+fn abbreviation() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "Toulouse",
+        Country => "France",
+        CountryCode => "FR",
+        HouseNumber => "17",
+        Postcode => "31000",
+        Road => "Avenue de la Gloire",
+    );
+
+    assert_eq!(
+        formatter
+            .format_with_config(
+                addr.clone(),
+                Configuration {
+                    abbreviate: Some(true),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+        r#"17 Av de la Gloire
+31000 Toulouse
+France
+"#
+        .to_owned()
+    );
+    // by default nothing is abbreviated
+    assert_eq!(
+        formatter.format(addr).unwrap(),
+        r#"17 Avenue de la Gloire
+31000 Toulouse
+France
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn abbreviation_of_several_components() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "London",
+        Country => "United Kingdom",
+        CountryCode => "GB",
+        HouseNumber => "10",
+        Postcode => "SW1A 2AA",
+        Road => "Downing Street",
+    );
+
+    assert_eq!(
+        formatter
+            .format_with_config(
+                addr,
+                Configuration {
+                    abbreviate: Some(true),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+        r#"10 Downing St
+London SW1A 2AA
+UK
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn abbreviation_with_forced_language() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "Bruxelles",
+        Country => "Belgique",
+        CountryCode => "BE",
+        HouseNumber => "12",
+        Postcode => "1050",
+        Road => "Avenue Louise",
+    );
+
+    // by default, the abbreviations of all the belgian languages are used
+    assert_eq!(
+        formatter
+            .format_with_config(
+                addr.clone(),
+                Configuration {
+                    abbreviate: Some(true),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+        r#"Av Louise 12
+1050 Bruxelles
+Belgique
+"#
+        .to_owned()
+    );
+    // there is no dutch abbreviation for 'Avenue'
+    assert_eq!(
+        formatter
+            .format_with_config(
+                addr,
+                Configuration {
+                    abbreviate: Some(true),
+                    language: Some("nl".to_owned()),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+        r#"Avenue Louise 12
+1050 Bruxelles
+Belgique
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn country_languages() {
+    let formatter = Formatter::default();
+    assert_eq!(
+        formatter.languages(&"BE".parse().unwrap()),
+        ["fr", "nl", "de"]
+    );
+    assert_eq!(formatter.languages(&"fr".parse().unwrap()), ["fr"]);
+    assert!(formatter.languages(&"XX".parse().unwrap()).is_empty());
+}
+
+#[test]
+fn config_from_dir() {
+    use Component::*;
+    let formatter = Formatter::from_config_dir("./address-formatting/conf").unwrap();
+    let addr_builder = PlaceBuilder::from_config_dir("./address-formatting/conf").unwrap();
+
+    let addr = addr_builder.build_place(vec![
+        ("city", "Toulouse".to_owned()),
+        ("country", "France".to_owned()),
+        ("country_code", "FR".to_owned()),
+        ("house_number", "17".to_owned()),
+        ("street", "Rue du Médecin-Colonel Calbairac".to_owned()),
+    ]);
+    assert_eq!(
+        addr[Road],
+        Some("Rue du Médecin-Colonel Calbairac".to_owned())
+    );
+
+    assert_eq!(
+        formatter.format(addr).unwrap(),
+        r#"17 Rue du Médecin-Colonel Calbairac
+Toulouse
+France
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn config_from_invalid_dir() {
+    assert!(Formatter::from_config_dir("./not_a_config_dir").is_err());
+    assert!(PlaceBuilder::from_config_dir("./not_a_config_dir").is_err());
+}
+
+#[test]
+fn formatted_lines() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let formatted = formatter
+        .format_lines(hashmap!(
+            City => "Toulouse",
+            Country => "France",
+            CountryCode => "FR",
+            HouseNumber => "17",
+            Postcode => "31000",
+            Road => "Rue du Médecin-Colonel Calbairac",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        formatted.lines(),
+        [
+            "17 Rue du Médecin-Colonel Calbairac",
+            "31000 Toulouse",
+            "France"
+        ]
+    );
+    assert_eq!(
+        formatted.single_line(" - "),
+        "17 Rue du Médecin-Colonel Calbairac - 31000 Toulouse - France"
+    );
+}
+
+#[test]
+fn empty_formatted_lines() {
+    let formatter = Formatter::default();
+    let formatted = formatter.format_lines(Place::default()).unwrap();
+    assert!(formatted.lines().is_empty());
+    assert_eq!(formatted.to_string(), "\n");
+}
+
+#[test]
+fn annotated_spans() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let annotated = formatter
+        .format_annotated(hashmap!(
+            City => "Washington",
+            Country => "US",
+            CountryCode => "US",
+            HouseNumber => "1600",
+            Postcode => "20500",
+            Road => "Pennsylvania  Avenue",
+            State => "District of Columbia",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        annotated.text,
+        "1600 Pennsylvania Avenue\nWashington, DC 20500\nUnited States of America\n"
+    );
+    let spans: Vec<_> = annotated
+        .spans
+        .iter()
+        .map(|(c, r)| (*c, &annotated.text[r.clone()]))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (HouseNumber, "1600"),
+            // the double space has been cleaned up
+            (Road, "Pennsylvania Avenue"),
+            (City, "Washington"),
+            (StateCode, "DC"),
+            (Postcode, "20500"),
+            // the country has been changed by a postformat rule
+            (Country, "United States of America"),
+        ]
+    );
+    assert_eq!(annotated.dropped, vec![State, CountryCode]);
+}
+
+#[test]
+fn annotated_deduplicated_values() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "Madrid",
+        Country => "España",
+        CountryCode => "ES",
+        HouseNumber => "1",
+        Postcode => "28013",
+        Road => "Calle Mayor",
+        State => "Madrid",
+    );
+    let annotated = formatter.format_annotated(addr.clone()).unwrap();
+
+    assert_eq!(annotated.text, formatter.format(addr).unwrap());
+    assert_eq!(
+        annotated.spans.iter().map(|(c, _)| *c).collect::<Vec<_>>(),
+        vec![Road, HouseNumber, Postcode, City, Country]
+    );
+    assert_eq!(annotated.dropped, vec![State, CountryCode]);
+}
+
+#[test]
+fn annotated_text_has_the_same_cleanup() {
+    use Component::*;
+    let formatter = Formatter::default();
+    // values with separators to cleanup, and duplicated tokens and lines
+    let addr = hashmap!(
+        Attention => " ,  Mairie ,, de Toulouse, ",
+        City => "Toulouse, Toulouse",
+        Country => "France\n",
+        CountryCode => "FR",
+        House => "Toulouse, Toulouse",
+        Postcode => "31000",
+        Road => "  Place   du\tCapitole , ",
+    );
+    let annotated = formatter.format_annotated(addr.clone()).unwrap();
+    assert_eq!(annotated.text, formatter.format(addr).unwrap());
+}
+
+#[test]
+fn domestic_and_international_mail() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "Toulouse",
+        Country => "France",
+        CountryCode => "FR",
+        HouseNumber => "17",
+        Postcode => "31000",
+        Road => "Rue du Médecin-Colonel Calbairac",
+    );
+    let format_from = |origin_country: &str, addr| {
+        formatter
+            .format_with_config(
+                addr,
+                Configuration {
+                    origin_country: Some(origin_country.to_owned()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+    };
+
+    assert_eq!(
+        format_from("FR", addr.clone()),
+        r#"17 Rue du Médecin-Colonel Calbairac
+31000 Toulouse
+"#
+        .to_owned()
+    );
+    assert_eq!(
+        format_from("DE", addr),
+        r#"17 Rue du Médecin-Colonel Calbairac
+31000 Toulouse
+FRANCE
+"#
+        .to_owned()
+    );
+
+    // Guadeloupe uses the french postal system
+    let addr = hashmap!(
+        City => "Pointe-à-Pitre",
+        Country => "Guadeloupe",
+        CountryCode => "GP",
+        Postcode => "97110",
+        Road => "Rue Frébault",
+    );
+    assert_eq!(
+        format_from("FR", addr),
+        r#"Rue Frébault
+97110 Pointe-à-Pitre
+"#
+        .to_owned()
+    );
+}
+
+#[test]
+fn invalid_origin_country() {
+    use address_formatter::ConfigurationError;
+    let addr = hashmap!(Component::City => "Toulouse", Component::CountryCode => "FR");
+    let error = Formatter::default()
+        .format_with_config(
+            addr,
+            Configuration {
+                origin_country: Some("France".to_owned()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+
+    // it is not a problem of the opencage configuration
+    assert!(error.downcast_ref::<ConfigurationError>().is_none());
+    assert_eq!(
+        error.to_string(),
+        "invalid origin_country: France is not a valid ISO3166-1:alpha2 country code"
+    );
+}
+
+#[test]
+fn single_line_and_short_labels() {
+    use address_formatter::AdminDepth;
+    use Component::*;
+    let formatter = Formatter::default();
+    let addr = hashmap!(
+        City => "Brooklyn",
+        Country => "United States of America",
+        CountryCode => "US",
+        County => "Kings County",
+        HouseNumber => "301",
+        Postcode => "11201",
+        Road => "Hicks Street",
+        State => "New York",
+        Suburb => "Brooklyn",
+    );
+    assert_eq!(
+        formatter
+            .format_single_line(addr.clone(), Default::default())
+            .unwrap(),
+        "301 Hicks Street, Brooklyn, NY 11201, United States of America"
+    );
+    let short = |depth| {
+        formatter
+            .format_short(addr.clone(), Default::default(), depth)
+            .unwrap()
+    };
+    assert_eq!(short(AdminDepth::Street), "Hicks Street");
+    assert_eq!(short(AdminDepth::Locality), "Hicks Street, Brooklyn");
+    assert_eq!(short(AdminDepth::State), "Hicks Street, Brooklyn, NY");
+    assert_eq!(
+        short(AdminDepth::Country),
+        "Hicks Street, Brooklyn, NY, United States of America"
+    );
+}
+
+#[test]
+fn single_line_follows_the_template_components() {
+    use address_formatter::AdminDepth;
+    use Component::*;
+    let formatter = Formatter::default();
+    // the city and the state are on the same line in Japan, the duplicated state is removed
+    let addr = hashmap!(
+        City => "Tokyo",
+        Country => "Japan",
+        CountryCode => "JP",
+        HouseNumber => "1",
+        Postcode => "100-0001",
+        Road => "Chuo",
+        State => "Tokyo",
+    );
+    assert_eq!(
+        formatter
+            .format_single_line(addr, Default::default())
+            .unwrap(),
+        "1 Chuo, Tokyo 100-0001, Japan"
+    );
+
+    // without city, the suburb is used as locality
+    let addr = hashmap!(
+        Country => "France",
+        CountryCode => "FR",
+        Road => "Rue Lepic",
+        Suburb => "Montmartre",
+    );
+    assert_eq!(
+        formatter
+            .format_short(addr, Default::default(), AdminDepth::Locality)
+            .unwrap(),
+        "Rue Lepic, Montmartre"
+    );
+}
+
+#[test]
+fn format_into_a_reused_buffer() {
+    use address_formatter::BorrowedPlace;
+    use Component::*;
+    let formatter = Formatter::default();
+    let conf = Configuration {
+        abbreviate: Some(true),
+        ..Default::default()
+    };
+    let road = "Avenue de la Gloire".to_owned();
+    let place: BorrowedPlace = vec![
+        (City, "Toulouse"),
+        (Country, "France"),
+        (CountryCode, "FR"),
+        (HouseNumber, "17"),
+        (Road, road.as_str()),
+    ]
+    .into_iter()
+    .collect();
+
+    let mut out = String::from("> ");
+    formatter
+        .format_into(place.clone(), &conf, &mut out)
+        .unwrap();
+    let owned = Place::from(place.clone());
+    formatter.format_into(&owned, &conf, &mut out).unwrap();
+    // the borrowed place is not changed by the abbreviation
+    assert_eq!(place[Road].as_deref(), Some("Avenue de la Gloire"));
+    assert_eq!(
+        out,
+        "> 17 Av de la Gloire\nToulouse\nFrance\n17 Av de la Gloire\nToulouse\nFrance\n"
+    );
+    assert_eq!(
+        formatter.format_with_config(owned, conf).unwrap(),
+        "17 Av de la Gloire\nToulouse\nFrance\n"
+    );
+}
+
+#[test]
+fn format_batch_keeps_the_order() {
+    use Component::*;
+    let formatter = Formatter::default();
+    let places: Vec<Place> = (0..200)
+        .map(|i| {
+            let number = i.to_string();
+            let country = ["FR", "DE", "US", "GB"][i % 4];
+            hashmap!(
+                City => "Somewhere",
+                CountryCode => country,
+                HouseNumber => number.as_str(),
+                Road => "Main Street",
+            )
+            .into()
+        })
+        .collect();
+
+    let formatted = formatter.format_batch(places.clone(), &Configuration::default());
+    assert_eq!(formatted.len(), places.len());
+    for (place, result) in places.into_iter().zip(formatted) {
+        assert_eq!(result.unwrap(), formatter.format(place).unwrap());
+    }
+}
@@ -0,0 +1,137 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{Component, FormatterBuilder, Place};
+
+fn render(template: &str, place: Place) -> String {
+    FormatterBuilder::default()
+        .address_template("DE", template)
+        .build()
+        .unwrap()
+        .format(place)
+        .unwrap()
+}
+
+fn error(template: &str) -> String {
+    let error = FormatterBuilder::default()
+        .address_template("DE", template)
+        .build()
+        .err()
+        .unwrap();
+    assert_eq!(error.issues.len(), 1);
+    error.issues[0].message.clone()
+}
+
+fn berlin() -> Place {
+    use Component::*;
+    hashmap!(
+        CountryCode => "DE",
+        Road => "Unter den Linden",
+        HouseNumber => "77",
+        City => "Berlin",
+    )
+    .into()
+}
+
+#[test]
+fn sections() {
+    assert_eq!(
+        render(
+            "{{#road}}{{{road}}} {{{house_number}}}\n{{/road}}{{{city}}}",
+            berlin()
+        ),
+        "Unter den Linden 77\nBerlin\n"
+    );
+    assert_eq!(
+        render("{{#suburb}}{{{suburb}}}\n{{/suburb}}{{{city}}}", berlin()),
+        "Berlin\n"
+    );
+    // an unknown variable has no value
+    assert_eq!(
+        render(
+            "{{#not_a_component}}{{{road}}}\n{{/not_a_component}}{{{city}}}",
+            berlin()
+        ),
+        "Berlin\n"
+    );
+    // like in handlebars, a blank value is true
+    let mut place = berlin();
+    place[Component::Suburb] = Some(" ".to_owned());
+    assert_eq!(
+        render("{{#suburb}}Mitte\n{{/suburb}}{{{city}}}", place.clone()),
+        "Mitte\nBerlin\n"
+    );
+    assert_eq!(
+        render("{{^suburb}}Mitte\n{{/suburb}}{{{city}}}", place),
+        "Berlin\n"
+    );
+}
+
+#[test]
+fn inverted_sections() {
+    assert_eq!(
+        render("{{^suburb}}{{{road}}}\n{{/suburb}}{{{city}}}", berlin()),
+        "Unter den Linden\nBerlin\n"
+    );
+    assert_eq!(
+        render("{{^road}}{{{suburb}}}\n{{/road}}{{{city}}}", berlin()),
+        "Berlin\n"
+    );
+}
+
+#[test]
+fn first_blocks() {
+    assert_eq!(
+        render(
+            "{{#first}} {{{suburb}}} || {{{city}}} || {{{town}}} {{/first}}",
+            berlin()
+        ),
+        "Berlin\n"
+    );
+    assert_eq!(
+        render(
+            "{{#first}} {{#road}}{{{road}}} {{{house_number}}}{{/road}} || {{{city}}} {{/first}}",
+            berlin()
+        ),
+        "Unter den Linden 77\n"
+    );
+}
+
+#[test]
+fn escaping() {
+    use Component::*;
+    let place: Place = hashmap!(
+        CountryCode => "DE",
+        Road => "<Linden> & \"Dom\"",
+    )
+    .into();
+    assert_eq!(
+        render("{{road}}", place.clone()),
+        "&lt;Linden&gt; &amp; &quot;Dom&quot;\n"
+    );
+    assert_eq!(render("{{{road}}}", place), "<Linden> & \"Dom\"\n");
+}
+
+#[test]
+fn comments() {
+    assert_eq!(
+        render(
+            "{{! the road }}{{{road}}}{{!-- and {{the}} city --}}",
+            berlin()
+        ),
+        "Unter den Linden\n"
+    );
+}
+
+#[test]
+fn malformed_templates() {
+    assert!(error("{{{road}}}\n{{{city}}").contains("unclosed expression '{{{city}}'"));
+    assert!(error("{{#first}} {{{road}}}").contains("'{{#first}}' without a '{{/first}}'"));
+    assert!(error("{{^road}}{{{city}}}").contains("'{{^road}}' without a '{{/road}}'"));
+    assert!(error("{{{road}}}{{/first}}").contains("'{{/first}}' without a '{{#first}}'"));
+    assert!(
+        error("{{#road}}{{{road}}}{{/city}}").contains("'{{/city}}' does not close '{{#road}}'")
+    );
+    assert!(error("{{#if road}}{{{road}}}{{/if}}").contains("unsupported expression '#if road'"));
+    assert!(error("{{> partial}}").contains("unsupported expression '> partial'"));
+    assert!(error("{{^first}}{{{road}}}{{/first}}").contains("unsupported expression '^first'"));
+}
@@ -0,0 +1,202 @@
+#[macro_use]
+extern crate maplit;
+use address_formatter::{Component, Configuration, Formatter, VCardAddress};
+use Component::*;
+
+#[test]
+fn vcard_round_trip() {
+    let formatter = Formatter::default();
+    let adr = formatter
+        .vcard_address(
+            hashmap!(
+                City => "Toulouse",
+                Country => "France",
+                CountryCode => "FR",
+                House => "Bâtiment A; 2ème étage",
+                HouseNumber => "17",
+                Postcode => "31000",
+                Road => "Rue du Médecin-Colonel Calbairac",
+                State => "Occitanie",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(adr.street, "17 Rue du Médecin-Colonel Calbairac");
+    let property = adr.to_property();
+    assert_eq!(
+        property,
+        r#"ADR;LABEL="Bâtiment A; 2ème étage\n17 Rue du Médecin-Colonel Calbairac\n31000 Toulouse\nFrance":;Bâtiment A\; 2ème étage;17 Rue du Médecin-Colonel Calbairac;Toulouse;Occitanie;31000;France"#
+    );
+
+    let parsed = VCardAddress::parse(&property).unwrap();
+    assert_eq!(parsed, adr);
+    let fr = Configuration {
+        country_code: Some("FR".to_owned()),
+        ..Default::default()
+    };
+    let place = formatter.place_from_vcard_address(&parsed, fr).unwrap();
+    assert_eq!(place[HouseNumber], Some("17".to_owned()));
+    assert_eq!(
+        place[Road],
+        Some("Rue du Médecin-Colonel Calbairac".to_owned())
+    );
+    assert_eq!(place[House], Some("Bâtiment A; 2ème étage".to_owned()));
+    assert_eq!(place[State], Some("Occitanie".to_owned()));
+    assert_eq!(place[CountryCode], Some("FR".to_owned()));
+}
+
+/// format the place as a vCard ADR, parse it and convert it back to a place
+fn round_trip(formatter: &Formatter, place: address_formatter::Place) -> address_formatter::Place {
+    let property = formatter
+        .vcard_address(place.clone(), Default::default())
+        .unwrap()
+        .to_property();
+    let conf = Configuration {
+        country_code: place[CountryCode].clone(),
+        ..Default::default()
+    };
+    formatter
+        .place_from_vcard_address(&VCardAddress::parse(&property).unwrap(), conf)
+        .unwrap()
+}
+
+#[test]
+fn vcard_round_trip_follows_the_template() {
+    let formatter = Formatter::default();
+
+    // the road ends with a number, but the house number is before the road in the US
+    let place: address_formatter::Place = hashmap!(
+        City => "Chicago",
+        Country => "United States of America",
+        CountryCode => "US",
+        Postcode => "60604",
+        Road => "Route 66",
+        State => "Illinois",
+    )
+    .into();
+    let parsed = round_trip(&formatter, place);
+    assert_eq!(parsed[Road], Some("Route 66".to_owned()));
+    assert_eq!(parsed[HouseNumber], None);
+
+    // the house number is after the road in Germany
+    let place: address_formatter::Place = hashmap!(
+        City => "Berlin",
+        Country => "Deutschland",
+        CountryCode => "DE",
+        HouseNumber => "77",
+        Postcode => "10117",
+        Road => "Unter den Linden",
+    )
+    .into();
+    let parsed = round_trip(&formatter, place.clone());
+    assert_eq!(parsed[Road], place[Road]);
+    assert_eq!(parsed[HouseNumber], place[HouseNumber]);
+    assert_eq!(parsed[CountryCode], Some("DE".to_owned()));
+
+    // the letter of a house number can take several bytes
+    let place: address_formatter::Place = hashmap!(
+        City => "Berlin",
+        CountryCode => "DE",
+        HouseNumber => "12ä",
+        Road => "Schönhauser Allee",
+    )
+    .into();
+    let parsed = round_trip(&formatter, place);
+    assert_eq!(parsed[Road], Some("Schönhauser Allee".to_owned()));
+    assert_eq!(parsed[HouseNumber], Some("12ä".to_owned()));
+}
+
+#[test]
+fn vcard_label_round_trip() {
+    let adr = VCardAddress {
+        street: "Rue des Remparts".to_owned(),
+        label: Some("C:\\new\\path ^n \"quoted\"\nRue des Remparts".to_owned()),
+        ..Default::default()
+    };
+    let property = adr.to_property();
+    assert_eq!(
+        property,
+        r#"ADR;LABEL="C:\\new\\path ^^n ^'quoted^'\nRue des Remparts":;;Rue des Remparts;;;;"#
+    );
+    assert_eq!(VCardAddress::parse(&property).unwrap(), adr);
+}
+
+#[test]
+fn vcard_parse() {
+    let adr = VCardAddress::parse(
+        r#"item1.ADR;TYPE=work;LABEL="Hicks ^'Street^'":;;3rd Avenue;Brooklyn;NY;11201;USA"#,
+    )
+    .unwrap();
+    assert_eq!(adr.label, Some(r#"Hicks "Street""#.to_owned()));
+    assert_eq!(adr.region, "NY");
+    let formatter = Formatter::default();
+    let us = Configuration {
+        country_code: Some("US".to_owned()),
+        ..Default::default()
+    };
+    let place = formatter.place_from_vcard_address(&adr, us).unwrap();
+    // "3rd" is not a house number
+    assert_eq!(place[Road], Some("3rd Avenue".to_owned()));
+    assert_eq!(place[HouseNumber], None);
+
+    let adr = VCardAddress::parse("ADR:;;Unter den Linden 77;Berlin;;10117;DE").unwrap();
+    assert_eq!(adr.label, None);
+    // the country is given by the ADR
+    let place = formatter
+        .place_from_vcard_address(&adr, Default::default())
+        .unwrap();
+    assert_eq!(place[HouseNumber], Some("77".to_owned()));
+    assert_eq!(place[Road], Some("Unter den Linden".to_owned()));
+
+    // an escaped '^' followed by a 'n' is not a new line
+    let adr = VCardAddress::parse(r#"ADR;LABEL="a^^n^nb^^^'":;;;;;;"#).unwrap();
+    assert_eq!(adr.label, Some("a^n\nb^\"".to_owned()));
+
+    assert!(VCardAddress::parse("TEL:+33 1 23 45 67 89").is_err());
+    assert!(VCardAddress::parse("ADR").is_err());
+}
+
+#[test]
+fn jcard() {
+    let adr = VCardAddress::parse("ADR:;;Unter den Linden 77;Berlin;;10117;Deutschland").unwrap();
+    assert_eq!(
+        adr.to_jcard(),
+        serde_json::json!([
+            "adr",
+            {},
+            "text",
+            [
+                "",
+                "",
+                "Unter den Linden 77",
+                "Berlin",
+                "",
+                "10117",
+                "Deutschland"
+            ]
+        ])
+    );
+}
+
+#[test]
+fn vcard_street_of_a_territory() {
+    // Guadeloupe uses the french rules, which add the country name after the territory
+    let adr = Formatter::default()
+        .vcard_address(
+            hashmap!(
+                City => "Pointe-à-Pitre",
+                Country => "Guadeloupe",
+                CountryCode => "GP",
+                HouseNumber => "3",
+                Postcode => "97110",
+                Road => "Rue Frébault",
+            ),
+            Default::default(),
+        )
+        .unwrap();
+    assert_eq!(adr.street, "3 Rue Frébault");
+    assert_eq!(
+        adr.label.as_deref(),
+        Some("3 Rue Frébault\n97110 Pointe-à-Pitre\nGuadeloupe, France")
+    );
+}
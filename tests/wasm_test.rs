@@ -0,0 +1,61 @@
+//! run with `cargo test --target wasm32-unknown-unknown --features wasm --test wasm_test`
+//! (with `wasm-bindgen-test-runner` as the runner of the wasm32 target)
+#![cfg(target_arch = "wasm32")]
+use address_formatter::wasm::{AdminDepth, Configuration, Formatter, PlaceBuilder};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn object(fields: &[(&str, JsValue)]) -> js_sys::Object {
+    let object = js_sys::Object::new();
+    for (k, v) in fields {
+        js_sys::Reflect::set(&object, &JsValue::from_str(k), v).unwrap();
+    }
+    object
+}
+
+#[wasm_bindgen_test]
+fn format_a_place() {
+    let formatter = Formatter::new().unwrap();
+    let place = PlaceBuilder::new().unwrap().build_place(&object(&[
+        ("street", "Rue du Médecin-Colonel Calbairac".into()),
+        ("house_number", 17.into()),
+        ("city", "Toulouse".into()),
+        ("country_code", "FR".into()),
+    ]));
+    assert_eq!(
+        place.get("road").unwrap().as_deref(),
+        Some("Rue du Médecin-Colonel Calbairac")
+    );
+    assert_eq!(place.get("house_number").unwrap().as_deref(), Some("17"));
+    assert!(place.get("not_a_component").is_err());
+
+    let mut conf = Configuration::new();
+    conf.set_abbreviate(Some(true));
+    assert_eq!(
+        formatter.format(&place, &conf).unwrap(),
+        "17 r du Médecin-Colonel Calbairac\nToulouse\n"
+    );
+    assert_eq!(
+        formatter
+            .format_single_line(&place, &Configuration::new())
+            .unwrap(),
+        "17 Rue du Médecin-Colonel Calbairac, Toulouse"
+    );
+    assert_eq!(
+        formatter
+            .format_short(&place, &Configuration::new(), AdminDepth::Locality)
+            .unwrap(),
+        "Rue du Médecin-Colonel Calbairac, Toulouse"
+    );
+}
+
+#[wasm_bindgen_test]
+fn invalid_configuration_throws() {
+    let formatter = Formatter::new().unwrap();
+    let place = PlaceBuilder::new()
+        .unwrap()
+        .build_place(&object(&[("city", "Toulouse".into())]));
+    let mut conf = Configuration::new();
+    conf.set_origin_country(Some("France".to_owned()));
+    assert!(formatter.format(&place, &conf).is_err());
+}